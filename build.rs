@@ -1,8 +1,9 @@
 use phf_codegen::Map;
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs::File;
+use std::hash::Hash;
 use std::io::{BufWriter, Write};
 use std::path::Path;
 
@@ -18,24 +19,41 @@ struct Table {
     values: HashMap<String, String>,
 }
 
-fn codegen_tables(mut out: BufWriter<File>) -> BufWriter<File> {
+#[derive(Deserialize, Default)]
+struct TableValueMeta {
+    status: Option<String>,
+    added: Option<String>,
+    removed: Option<String>,
+    notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TableTypes {
+    hl7: Vec<u16>,
+    user: Vec<u16>,
+}
+
+/// Write out the tables, returning the numbers of the tables that were written so that
+/// sub-fields can link to them
+fn codegen_tables(mut out: BufWriter<File>) -> (BufWriter<File>, HashSet<usize>) {
     if std::env::var("CARGO_FEATURE_TABLES").is_err() {
         p!("Tables feature not enabled; tables will NOT be available");
-        let table_refs: Map<u16> = Map::new();
         let table_descriptions: Map<u16> = Map::new();
+        let table_types: Map<u16> = Map::new();
+        out = write_tables(out, &HashMap::new(), &HashMap::new());
         writeln!(
             &mut out,
-            "pub static TABLES: phf::Map<u16, &'static TableValues> = {};",
-            table_refs.build()
+            "pub static TABLE_DESCRIPTIONS: phf::Map<u16, &'static str> = {};",
+            table_descriptions.build()
         )
         .expect("can write to codegen.rs");
         writeln!(
             &mut out,
-            "pub static TABLE_DESCRIPTIONS: phf::Map<u16, &'static str> = {};",
-            table_descriptions.build()
+            "pub static TABLE_TYPES: phf::Map<u16, TableType> = {};",
+            table_types.build()
         )
         .expect("can write to codegen.rs");
-        return out;
+        return (out, HashSet::new());
     }
 
     let tables =
@@ -65,18 +83,331 @@ fn codegen_tables(mut out: BufWriter<File>) -> BufWriter<File> {
     )
     .expect("can write to codegen.rs");
 
+    let table_value_meta = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/assets/table_value_meta.json"
+    ))
+    .expect("can open ./assets/table_value_meta.json");
+    let table_value_meta: HashMap<u16, HashMap<String, TableValueMeta>> =
+        serde_json::from_str(&table_value_meta).expect("can parse table value metadata");
+    for (table, meta) in table_value_meta.iter() {
+        let values = &tables
+            .get(table)
+            .unwrap_or_else(|| panic!("table value metadata given for unknown table {table}"))
+            .values;
+        if let Some(code) = meta.keys().find(|code| !values.contains_key(*code)) {
+            panic!("table value metadata given for unknown code {code:?} in table {table}");
+        }
+    }
+
+    out = write_tables(out, &tables, &table_value_meta);
+    let table_types = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/assets/table_types.json"
+    ))
+    .expect("can open ./assets/table_types.json");
+    let table_types: TableTypes =
+        serde_json::from_str(&table_types).expect("can parse table types");
+    let mut table_types_map = Map::new();
+    for (ids, table_type) in [
+        (&table_types.hl7, "TableType::Hl7"),
+        (&table_types.user, "TableType::User"),
+    ] {
+        for table in ids.iter().filter(|t| tables.contains_key(t)) {
+            table_types_map.entry(*table, table_type);
+        }
+    }
+    writeln!(
+        &mut out,
+        "pub static TABLE_TYPES: phf::Map<u16, TableType> = {};",
+        table_types_map.build()
+    )
+    .expect("can write to codegen.rs");
+
+    if std::env::var("CARGO_FEATURE_TABLE_ENUMS").is_ok() {
+        write_table_enums(&tables, &table_types);
+    }
+
+    (out, tables.keys().map(|table| *table as usize).collect())
+}
+
+#[derive(Deserialize, Default)]
+struct TableEnumNames {
+    /// The name of the enum, instead of one derived from the table's description
+    name: Option<String>,
+    /// Variant names by code, instead of ones derived from the values' descriptions
+    #[serde(default)]
+    values: HashMap<String, String>,
+}
+
+/// Turn a description into a `PascalCase` identifier, using at most the first eight words
+fn pascal_case(description: &str) -> String {
+    description
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .take(8)
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars
+                .next()
+                .expect("words aren't empty")
+                .to_ascii_uppercase();
+            std::iter::once(first)
+                .chain(chars.map(|c| c.to_ascii_lowercase()))
+                .collect::<String>()
+        })
+        .collect()
+}
+
+/// Escape text from the tables for a doc comment, so that rustdoc doesn't read markup in it (such
+/// as the `<p>` some descriptions contain) as HTML, code spans or links
+fn doc_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '`' | '[' | ']' | '*' | '_' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\r' | '\n' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Which tables to generate enums for: every HL7-defined table, unless
+/// `HL7_DEFINITIONS_TABLE_ENUMS` lists the tables to generate (where `hl7` stands for every
+/// HL7-defined table)
+fn selected_table_enums(tables: &HashMap<u16, Table>, table_types: &TableTypes) -> Vec<u16> {
+    println!("cargo:rerun-if-env-changed=HL7_DEFINITIONS_TABLE_ENUMS");
+    let mut selected = match std::env::var("HL7_DEFINITIONS_TABLE_ENUMS") {
+        Err(_) => table_types.hl7.clone(),
+        Ok(list) => list
+            .split(',')
+            .map(|table| table.trim())
+            .filter(|table| !table.is_empty())
+            .flat_map(|table| {
+                if table.eq_ignore_ascii_case("hl7") {
+                    return table_types.hl7.clone();
+                }
+                let number = match table.get(..3) {
+                    Some(prefix) if prefix.eq_ignore_ascii_case("HL7") => &table[3..],
+                    _ => table,
+                };
+                match number.parse::<u16>() {
+                    Ok(number) => vec![number],
+                    Err(_) => {
+                        panic!("can parse {table} in HL7_DEFINITIONS_TABLE_ENUMS as a table number")
+                    }
+                }
+            })
+            .collect(),
+    };
+    selected.sort();
+    selected.dedup();
+    selected.retain(|table| match tables.get(table) {
+        Some(t) if !t.values.is_empty() => true,
+        Some(_) => false,
+        None => {
+            p!("Table {table} doesn't exist, no enum will be generated for it");
+            false
+        }
+    });
+    selected
+}
+
+/// Write out enums for the selected tables to `table_enums.rs`, which `src/codes.rs` includes
+fn write_table_enums(tables: &HashMap<u16, Table>, table_types: &TableTypes) {
+    let names = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/assets/table_enum_names.json"
+    ))
+    .expect("can open ./assets/table_enum_names.json");
+    let names: HashMap<u16, TableEnumNames> =
+        serde_json::from_str(&names).expect("can parse table enum names");
+
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join("table_enums.rs");
+    let mut out = BufWriter::new(File::create(path).unwrap());
+    let mut enum_names = HashSet::new();
+    for table in selected_table_enums(tables, table_types) {
+        let t = &tables[&table];
+        let overrides = names.get(&table);
+        // user-defined tables are routinely extended locally, so they have a fallback variant
+        let open = !table_types.hl7.contains(&table);
+
+        let mut name = overrides
+            .and_then(|o| o.name.clone())
+            .unwrap_or_else(|| pascal_case(&t.desc));
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            name = format!("Table{name}");
+        }
+        if name == "Self" || !enum_names.insert(name.clone()) {
+            name = format!("{name}{table:04}");
+            enum_names.insert(name.clone());
+        }
+
+        let mut codes = t.values.keys().collect::<Vec<_>>();
+        codes.sort();
+        let mut variants: Vec<(&str, String, &str)> = Vec::with_capacity(codes.len());
+        let mut variant_names: HashSet<String> = HashSet::new();
+        if open {
+            variant_names.insert("Other".to_string());
+        }
+        for code in codes {
+            let description = t.values[code].as_str();
+            let mut variant = overrides
+                .and_then(|o| o.values.get(code).cloned())
+                .unwrap_or_else(|| pascal_case(description));
+            if variant.is_empty() || variant.starts_with(|c: char| c.is_ascii_digit()) {
+                variant = format!("Code{}", pascal_case(code));
+            }
+            if variant == "Self" || variant_names.contains(&variant) {
+                variant = format!("{variant}{}", pascal_case(code));
+            }
+            if !variant_names.insert(variant.clone()) {
+                panic!("table {table} has more than one value named {variant}");
+            }
+            variants.push((code, variant, description));
+        }
+
+        let derives = if open {
+            "Clone, PartialEq, Eq, Hash, Debug"
+        } else {
+            "Copy, Clone, PartialEq, Eq, Hash, Debug"
+        };
+        writeln!(
+            out,
+            "/// {} (HL7 table {table:04})\n#[derive({derives})]\npub enum {name} {{",
+            doc_text(&t.desc)
+        )
+        .expect("can write to table_enums.rs");
+        for (code, variant, description) in variants.iter() {
+            writeln!(
+                out,
+                "    /// `{code}`: {}\n    {variant},",
+                doc_text(description)
+            )
+            .expect("can write to table_enums.rs");
+        }
+        if open {
+            writeln!(
+                out,
+                "    /// A code that isn't in the table, such as a locally-defined one\n    Other(alloc::string::String),"
+            )
+            .expect("can write to table_enums.rs");
+        }
+        writeln!(out, "}}").expect("can write to table_enums.rs");
+
+        let as_code = variants
+            .iter()
+            .map(|(code, variant, _)| format!("{name}::{variant} => r#\"{code}\"#,"))
+            .collect::<String>();
+        let from_code = variants
+            .iter()
+            .map(|(code, variant, _)| format!("r#\"{code}\"# => {name}::{variant},"))
+            .collect::<String>();
+        let descriptions = variants
+            .iter()
+            .map(|(_, variant, description)| format!("{name}::{variant} => r#\"{description}\"#,"))
+            .collect::<String>();
+        if open {
+            writeln!(
+                out,
+                r#"impl {name} {{
+    /// The table the enum was generated from
+    pub const TABLE: TableId = TableId({table});
+
+    /// The code for the value, as it appears in a message
+    pub fn as_code(&self) -> &str {{
+        match self {{ {as_code} {name}::Other(code) => code }}
+    }}
+
+    /// The description of the value from the table, or `None` if it isn't in the table
+    pub fn description(&self) -> Option<&'static str> {{
+        Some(match self {{ {descriptions} {name}::Other(_) => return None }})
+    }}
+}}
+
+impl core::str::FromStr for {name} {{
+    type Err = core::convert::Infallible;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {{
+        Ok(match code {{ {from_code} code => {name}::Other(code.into()) }})
+    }}
+}}"#
+            )
+        } else {
+            writeln!(
+                out,
+                r#"impl {name} {{
+    /// The table the enum was generated from
+    pub const TABLE: TableId = TableId({table});
+
+    /// The code for the value, as it appears in a message
+    pub fn as_code(&self) -> &'static str {{
+        match self {{ {as_code} }}
+    }}
+
+    /// The description of the value from the table
+    pub fn description(&self) -> &'static str {{
+        match self {{ {descriptions} }}
+    }}
+}}
+
+impl core::str::FromStr for {name} {{
+    type Err = UnknownCode;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {{
+        Ok(match code {{ {from_code} _ => return Err(UnknownCode {{ table: Self::TABLE }}) }})
+    }}
+}}"#
+            )
+        }
+        .expect("can write to table_enums.rs");
+        writeln!(
+            out,
+            r#"impl core::fmt::Display for {name} {{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {{
+        write!(f, "{{}}", self.as_code())
+    }}
+}}"#
+        )
+        .expect("can write to table_enums.rs");
+    }
+}
+
+/// Write out every table's values as static data, along with the `TABLES` map and a
+/// `TABLE_DEF_*` static for each table that sub-fields can link to
+#[cfg(not(feature = "compressed"))]
+fn write_tables(
+    mut out: BufWriter<File>,
+    tables: &HashMap<u16, Table>,
+    table_value_meta: &HashMap<u16, HashMap<String, TableValueMeta>>,
+) -> BufWriter<File> {
     for (table, v) in tables.iter() {
         let mut values = Map::new();
         for (k, v) in v.values.iter() {
-            values.entry(k, &format!("r#\"{v}\"#"));
+            let meta = table_value_meta.get(table).and_then(|meta| meta.get(k));
+            values.entry(k, &format_table_value(v, meta));
         }
         writeln!(
             &mut out,
-            "pub static TABLE_{table}: phf::Map<&'static str, &'static str> = {};",
+            "pub static TABLE_{table}: TableValues = {};",
             values.build()
         )
         .expect("can write to codegen.rs");
+        writeln!(
+            &mut out,
+            "static TABLE_DEF_{table}: Table = Table {{ id: TableId({table}), description: r#\"{}\"#, values: &TABLE_{table} }};",
+            v.desc
+        )
+        .expect("can write to codegen.rs");
     }
+
     let mut table_refs = Map::new();
     for table in tables.keys() {
         table_refs.entry(table, &format!("&TABLE_{table}"));
@@ -87,10 +418,105 @@ fn codegen_tables(mut out: BufWriter<File>) -> BufWriter<File> {
         table_refs.build()
     )
     .expect("can write to codegen.rs");
+    out
+}
 
+/// Write every table's values out as a deflated blob that is decoded the first time a table is
+/// accessed; see `decode_tables` in `src/compressed.rs` for the decoder, which must be kept in
+/// sync with this
+#[cfg(feature = "compressed")]
+fn write_tables(
+    mut out: BufWriter<File>,
+    tables: &HashMap<u16, Table>,
+    table_value_meta: &HashMap<u16, HashMap<String, TableValueMeta>>,
+) -> BufWriter<File> {
+    let default_meta = TableValueMeta::default();
+    let mut buf = Vec::new();
+    let ids = tables.keys().copied().collect::<Vec<u16>>();
+    let state = phf_generator::generate_hash(&ids);
+    hash_state(&mut buf, &state);
+    varint(&mut buf, state.map.len());
+    for index in state.map {
+        let id = ids[index];
+        varint(&mut buf, id as usize);
+        let values = tables[&id].values.iter().collect::<Vec<_>>();
+        map(
+            &mut buf,
+            &values,
+            |(code, _)| code,
+            |buf, (code, description)| {
+                let meta = table_value_meta
+                    .get(&id)
+                    .and_then(|meta| meta.get(*code))
+                    .unwrap_or(&default_meta);
+                let status = match table_value_status(description, meta) {
+                    "Active" => 0,
+                    "BackwardCompatibility" => 1,
+                    "Deprecated" => 2,
+                    _ => 3,
+                };
+                string(buf, code);
+                string(buf, description);
+                varint(buf, status);
+                for s in [&meta.added, &meta.removed, &meta.notes] {
+                    match s {
+                        Some(s) => {
+                            varint(buf, 1);
+                            string(buf, s);
+                        }
+                        None => varint(buf, 0),
+                    }
+                }
+            },
+        );
+    }
+
+    let compressed = miniz_oxide::deflate::compress_to_vec(&buf, 10);
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join("tables.bin");
+    std::fs::write(path, compressed).expect("can write tables.bin");
+    writeln!(
+        &mut out,
+        r#"pub static TABLES_BLOB: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/tables.bin"));"#
+    )
+    .expect("can write to codegen.rs");
     out
 }
 
+/// The name of the `ValueStatus` variant for a table value
+fn table_value_status(description: &str, meta: &TableValueMeta) -> &'static str {
+    // the source data notes retired codes in their descriptions, so use that unless the
+    // status has been spelled out explicitly
+    let lower = description.to_lowercase();
+    match meta.status.as_deref() {
+        Some("active") => "Active",
+        Some("backward") => "BackwardCompatibility",
+        Some("deprecated") => "Deprecated",
+        Some("withdrawn") => "Withdrawn",
+        Some(status) => panic!("unknown table value status {status}"),
+        None if lower.contains("deprecated") => "Deprecated",
+        None if lower.contains("backward compatibility") => "BackwardCompatibility",
+        None => "Active",
+    }
+}
+
+#[cfg(not(feature = "compressed"))]
+fn format_table_value(description: &str, meta: Option<&TableValueMeta>) -> String {
+    let default_meta = TableValueMeta::default();
+    let meta = meta.unwrap_or(&default_meta);
+    let status = format!("ValueStatus::{}", table_value_status(description, meta));
+    let optional = |s: &Option<String>| match s {
+        Some(s) => format!(r##"Some(r#"{s}"#)"##),
+        None => "None".to_string(),
+    };
+    let added = optional(&meta.added);
+    let removed = optional(&meta.removed);
+    let notes = optional(&meta.notes);
+
+    format!(
+        r##"TableValue {{ description: r#"{description}"#, status: {status}, added: {added}, removed: {removed}, notes: {notes} }}"##
+    )
+}
+
 #[derive(Deserialize)]
 struct Definition {
     fields: HashMap<String, Field>,
@@ -112,6 +538,15 @@ struct SubField {
     rep: usize,
     len: Option<usize>,
     table: Option<usize>,
+    /// The conformance length, from `field_lengths.json`
+    #[serde(default)]
+    conf_len: Option<usize>,
+    /// Whether the value may be truncated to its conformance length, from `field_lengths.json`
+    #[serde(default)]
+    truncate: Option<bool>,
+    /// The data element's item number, from `item_numbers.json`
+    #[serde(default)]
+    item: Option<usize>,
 }
 
 #[derive(Deserialize)]
@@ -152,153 +587,454 @@ struct MessageCompound {
     max: usize,
 }
 
-fn format_message_segment(s: &MessageSegment) -> String {
-    let MessageSegment {
+/// Deduplicates values so that each distinct one is only emitted once, no matter how many
+/// versions share it
+struct Pool<T> {
+    indices: HashMap<T, usize>,
+    items: Vec<T>,
+}
+
+impl<T> Default for Pool<T> {
+    fn default() -> Self {
+        Pool {
+            indices: HashMap::new(),
+            items: Vec::new(),
+        }
+    }
+}
+
+impl<T: Clone + Eq + Hash> Pool<T> {
+    fn intern(&mut self, item: T) -> usize {
+        if let Some(index) = self.indices.get(&item) {
+            return *index;
+        }
+        let index = self.items.len();
+        self.indices.insert(item.clone(), index);
+        self.items.push(item);
+        index
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct InternedSubField {
+    datatype: usize,
+    desc: usize,
+    opt: usize,
+    rep: usize,
+    len: Option<usize>,
+    table: Option<usize>,
+    conf_len: Option<usize>,
+    truncate: Option<bool>,
+    item: Option<usize>,
+    /// The index of the sub-field's datatype in the field pool
+    link: Option<usize>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct InternedMessageSegment {
+    name: usize,
+    desc: usize,
+    min: usize,
+    max: usize,
+    children: Option<usize>,
+    compounds: Option<usize>,
+    /// The index of the segment's definition in the segment pool
+    link: Option<usize>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct InternedMessageCompound {
+    name: Option<usize>,
+    desc: usize,
+    min: usize,
+    max: usize,
+}
+
+/// A sub-field list or field, in the order the [Interner] created them. Everything an item
+/// links to is created before it, so they can be emitted in this order and only ever refer
+/// backwards.
+#[derive(Clone, Copy)]
+enum Created {
+    SubFields(usize),
+    Field(usize),
+}
+
+/// The definitions for a single version, with every string and list replaced by its index in
+/// the [Interner]'s pools
+struct InternedVersion {
+    version: String,
+    /// `(id, field)`
+    fields: Vec<(String, usize)>,
+    /// `(id, segment)`
+    segments: Vec<(String, usize)>,
+    /// `(id, name, description, segments)`
+    messages: Vec<(String, usize, usize, usize)>,
+}
+
+/// The fields of a version which have been interned so far, by ID; `None` while a field is
+/// still being interned
+type FieldLinks<'a> = HashMap<&'a str, Option<usize>>;
+
+/// Shares strings and lists between all the versions so that data which doesn't change from
+/// version to version is only stored once
+#[derive(Default)]
+struct Interner {
+    strings: Pool<String>,
+    subfields: Pool<Vec<InternedSubField>>,
+    /// `(description, subfields)`
+    fields: Pool<(usize, usize)>,
+    /// `(description, fields)`
+    segments: Pool<(usize, usize)>,
+    message_compounds: Pool<Vec<InternedMessageCompound>>,
+    message_segments: Pool<Vec<InternedMessageSegment>>,
+    created: Vec<Created>,
+    versions: Vec<InternedVersion>,
+}
+
+impl Interner {
+    fn string(&mut self, s: &str) -> usize {
+        self.strings.intern(s.to_string())
+    }
+
+    fn subfields<'a>(
+        &mut self,
+        definition: &'a Definition,
+        subfields: &'a [SubField],
+        links: &mut FieldLinks<'a>,
+    ) -> usize {
+        let subfields = subfields
+            .iter()
+            .map(|s| InternedSubField {
+                datatype: self.string(&s.datatype),
+                desc: self.string(&s.desc),
+                opt: s.opt,
+                rep: s.rep,
+                len: s.len,
+                table: s.table,
+                conf_len: s.conf_len,
+                truncate: s.truncate,
+                item: s.item,
+                link: self.field(definition, &s.datatype, links),
+            })
+            .collect();
+        let created = self.subfields.items.len();
+        let index = self.subfields.intern(subfields);
+        if index == created {
+            self.created.push(Created::SubFields(index));
+        }
+        index
+    }
+
+    /// Intern a field along with every field its sub-fields link to, returning its index in
+    /// the field pool. Returns `None` if the field isn't defined, or if it is still being
+    /// interned further up, as linking to it would then create a cycle.
+    fn field<'a>(
+        &mut self,
+        definition: &'a Definition,
+        id: &'a str,
+        links: &mut FieldLinks<'a>,
+    ) -> Option<usize> {
+        if let Some(link) = links.get(id) {
+            return *link;
+        }
+        let field = definition.fields.get(id)?;
+        links.insert(id, None);
+        let desc = self.string(&field.desc);
+        let subfields = self.subfields(definition, &field.subfields, links);
+        let created = self.fields.items.len();
+        let index = self.fields.intern((desc, subfields));
+        if index == created {
+            self.created.push(Created::Field(index));
+        }
+        links.insert(id, Some(index));
+        Some(index)
+    }
+
+    fn message_compounds(&mut self, compounds: &[MessageCompound]) -> usize {
+        let compounds = compounds
+            .iter()
+            .map(|c| InternedMessageCompound {
+                name: c.name.as_deref().map(|name| self.string(name)),
+                desc: self.string(&c.desc),
+                min: c.min,
+                max: c.max,
+            })
+            .collect();
+        self.message_compounds.intern(compounds)
+    }
+
+    /// Children are always interned before their parents, so a list only ever refers to lists
+    /// with a lower index
+    fn message_segments(
+        &mut self,
+        segments: &[MessageSegment],
+        links: &HashMap<&str, usize>,
+    ) -> usize {
+        let segments = segments
+            .iter()
+            .map(|s| InternedMessageSegment {
+                name: self.string(&s.name),
+                desc: self.string(&s.desc),
+                min: s.min,
+                max: s.max,
+                children: s
+                    .children
+                    .as_deref()
+                    .map(|children| self.message_segments(children, links)),
+                compounds: s
+                    .compounds
+                    .as_deref()
+                    .map(|compounds| self.message_compounds(compounds)),
+                link: match s.children {
+                    Some(_) => None,
+                    None => links.get(s.name.as_str()).copied(),
+                },
+            })
+            .collect();
+        self.message_segments.intern(segments)
+    }
+
+    fn version(&mut self, version: &str, definition: &Definition) -> InternedVersion {
+        // sort everything so the generated data doesn't depend on hash map iteration order
+        let mut fields = definition.fields.keys().collect::<Vec<_>>();
+        fields.sort();
+        let mut segments = definition.segments.iter().collect::<Vec<_>>();
+        segments.sort_by_key(|(id, _)| id.as_str());
+        let mut messages = definition.messages.iter().collect::<Vec<_>>();
+        messages.sort_by_key(|(id, _)| id.as_str());
+
+        let mut links = FieldLinks::new();
+        let fields = fields
+            .into_iter()
+            .map(|id| {
+                let field = self
+                    .field(definition, id, &mut links)
+                    .expect("top-level fields can always be interned");
+                (id.clone(), field)
+            })
+            .collect();
+        let segments = segments
+            .into_iter()
+            .map(|(id, s)| {
+                let desc = self.string(&s.desc);
+                let fields = self.subfields(definition, &s.fields, &mut links);
+                (id.clone(), self.segments.intern((desc, fields)))
+            })
+            .collect::<Vec<_>>();
+        let segment_links = segments
+            .iter()
+            .map(|(id, segment)| (id.as_str(), *segment))
+            .collect::<HashMap<_, _>>();
+        let messages = messages
+            .into_iter()
+            .map(|(id, m)| {
+                (
+                    id.clone(),
+                    self.string(&m.name),
+                    self.string(&m.desc),
+                    self.message_segments(&m.segments.segments, &segment_links),
+                )
+            })
+            .collect();
+
+        InternedVersion {
+            version: version.to_string(),
+            fields,
+            segments,
+            messages,
+        }
+    }
+}
+
+#[cfg(not(feature = "compressed"))]
+fn format_option(value: Option<usize>, format: impl Fn(usize) -> String) -> String {
+    match value {
+        Some(value) => format!("Some({})", format(value)),
+        None => "None".to_string(),
+    }
+}
+
+#[cfg(not(feature = "compressed"))]
+fn format_subfield(s: &InternedSubField, tables: &HashSet<usize>) -> String {
+    let InternedSubField {
+        datatype,
+        desc,
+        opt,
+        rep,
+        len,
+        table,
+        conf_len,
+        truncate,
+        item,
+        link,
+    } = s;
+    let opt = map_optionality(*opt);
+    let rep = match rep {
+        0 => "FieldRepeatability::Unbounded".to_string(),
+        1 => "FieldRepeatability::Single".to_string(),
+        n => format!("FieldRepeatability::Bounded({n})"),
+    };
+    let len = format_option(*len, |len| len.to_string());
+    let conf_len = format_option(*conf_len, |len| len.to_string());
+    let item = format_option(*item, |item| item.to_string());
+    let truncation = match truncate {
+        None => "None",
+        Some(true) => "Some(Truncation::Allowed)",
+        Some(false) => "Some(Truncation::NotAllowed)",
+    };
+    let datatype_link = format_option(*link, |field| format!("&FIELD_{field}"));
+    let table_link = format_option(table.filter(|table| tables.contains(table)), |table| {
+        format!("&TABLE_DEF_{table}")
+    });
+    let table = format_option(*table, |table| table.to_string());
+    format!("SubField {{ datatype: STR_{datatype}, description: STR_{desc}, optionality: {opt}, repeatability: {rep}, max_length: {len}, conformance_length: {conf_len}, truncation: {truncation}, table: {table}, item_number: {item}, datatype_link: Link({datatype_link}), table_link: Link({table_link}) }}")
+}
+
+#[cfg(not(feature = "compressed"))]
+fn format_field((desc, subfields): &(usize, usize)) -> String {
+    format!("Field {{ description: STR_{desc}, subfields: &SUBFIELDS_{subfields} }}")
+}
+
+#[cfg(not(feature = "compressed"))]
+fn format_segment((desc, fields): &(usize, usize)) -> String {
+    format!("Segment {{ description: STR_{desc}, fields: &SUBFIELDS_{fields} }}")
+}
+
+#[cfg(not(feature = "compressed"))]
+fn format_message_compound(c: &InternedMessageCompound) -> String {
+    let InternedMessageCompound {
+        name,
+        desc,
+        min,
+        max,
+    } = c;
+    let name = format_option(*name, |name| format!("STR_{name}"));
+    format!("MessageCompound {{ name: {name}, description: STR_{desc}, min: {min}, max: {max} }}")
+}
+
+#[cfg(not(feature = "compressed"))]
+fn format_message_segment(s: &InternedMessageSegment) -> String {
+    let InternedMessageSegment {
         name,
         desc,
         min,
         max,
         children,
         compounds,
+        link,
     } = s;
+    let children = format_option(*children, |children| {
+        format!("&MESSAGE_SEGMENTS_{children}")
+    });
+    let compounds = format_option(*compounds, |compounds| {
+        format!("&MESSAGE_COMPOUNDS_{compounds}")
+    });
+    let link = format_option(*link, |segment| format!("&SEGMENT_{segment}"));
+    format!("MessageSegment {{ name: STR_{name}, description: STR_{desc}, min: {min}, max: {max}, children: {children}, compounds: {compounds}, segment_link: Link({link}) }}")
+}
 
-    let children = match children {
-        Some(children) => {
-            let children = children
-                .iter()
-                .map(format_message_segment)
-                .collect::<Vec<String>>()
-                .join(", ");
-            format!("Some(&[{children}])")
-        }
-        None => "None".into(),
-    };
-
-    let compounds = match compounds {
-        Some(compounds) => {
-            let compounds = compounds
+#[cfg(not(feature = "compressed"))]
+fn write_pool<T>(
+    out: &mut BufWriter<File>,
+    name: &str,
+    ty: &str,
+    pool: &Pool<Vec<T>>,
+    format: impl Fn(&T) -> String,
+) {
+    for (i, items) in pool.items.iter().enumerate() {
+        writeln!(
+            out,
+            "static {name}_{i}: [{ty}; {}] = [{}];",
+            items.len(),
+            items
                 .iter()
-                .map(|c| {
-                    let MessageCompound { name, desc, min, max } = c;
-                    let name = match name {
-                        Some(n) => format!(r##"Some(r#"{n}"#)"##),
-                        None => "None".into()
-                    };
-                    format!(r##"MessageCompound {{ name: {name}, description: r#"{desc}"#, min: {min}, max: {max} }}"##)
-                })
+                .map(&format)
                 .collect::<Vec<String>>()
-                .join(", ");
-            format!("Some(&[{compounds}])")
-        }
-        None => "None".into(),
-    };
-
-    format!(
-        r##"MessageSegment {{ name: r#"{name}"#, description: r#"{desc}"#, min: {min}, max: {max}, children: {children}, compounds: {compounds} }}"##
-    )
+                .join(", ")
+        )
+        .expect("can write to codegen.rs");
+    }
 }
 
-fn codegen_definitions(mut out: BufWriter<File>) -> BufWriter<File> {
-    let definitions =
-        std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/defs.json"))
-            .expect("can open ./assets/defs.json");
-    let definitions: HashMap<String, Definition> =
-        serde_json::from_str(&definitions).expect("can parse definitions");
+/// Write the interned definitions out as static data
+#[cfg(not(feature = "compressed"))]
+fn write_definitions(
+    mut out: BufWriter<File>,
+    interner: &Interner,
+    tables: &HashSet<usize>,
+) -> BufWriter<File> {
+    for (i, s) in interner.strings.items.iter().enumerate() {
+        writeln!(&mut out, "const STR_{i}: &str = r#\"{s}\"#;").expect("can write to codegen.rs");
+    }
+    for created in interner.created.iter() {
+        match *created {
+            Created::SubFields(i) => {
+                let subfields = &interner.subfields.items[i];
+                writeln!(
+                    &mut out,
+                    "static SUBFIELDS_{i}: [SubField; {}] = [{}];",
+                    subfields.len(),
+                    subfields
+                        .iter()
+                        .map(|s| format_subfield(s, tables))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )
+            }
+            Created::Field(i) => writeln!(
+                &mut out,
+                "static FIELD_{i}: Field = {};",
+                format_field(&interner.fields.items[i])
+            ),
+        }
+        .expect("can write to codegen.rs");
+    }
+    for (i, segment) in interner.segments.items.iter().enumerate() {
+        writeln!(
+            &mut out,
+            "static SEGMENT_{i}: Segment = {};",
+            format_segment(segment)
+        )
+        .expect("can write to codegen.rs");
+    }
+    write_pool(
+        &mut out,
+        "MESSAGE_COMPOUNDS",
+        "MessageCompound",
+        &interner.message_compounds,
+        format_message_compound,
+    );
+    write_pool(
+        &mut out,
+        "MESSAGE_SEGMENTS",
+        "MessageSegment",
+        &interner.message_segments,
+        format_message_segment,
+    );
 
     let mut definitions_map = Map::new();
-    'versions: for (version, definitions) in definitions.iter() {
-        if std::env::var(format!("CARGO_FEATURE_{}", version.replace('.', ""))).is_err() {
-            p!("Version {version} feature disabled, version {version} will NOT be available!");
-            continue 'versions;
-        }
+    for version in interner.versions.iter() {
+        let version_name = version.version.replace('.', "_");
 
-        let version_name = version.replace('.', "_");
         let mut fields = Map::new();
-        for (field_id, field) in definitions.fields.iter() {
-            let subfields = field
-                .subfields
-                .iter()
-                .map(|s| {
-                    let SubField {
-                        datatype,
-                        desc,
-                        opt,
-                        rep,
-                        len,
-                        table,
-                    } = s;
-                    let opt = map_optionality(*opt);
-                    let rep = match rep {
-                        0 => "FieldRepeatability::Unbounded".to_string(),
-                        1 => "FieldRepeatability::Single".to_string(),
-                        n => format!("FieldRepeatability::Bounded({n})"),
-                    };
-                    let len = match len {
-                        Some(len) => format!("Some({len})"),
-                        None => "None".to_string(),
-                    };
-                    let table = match table {
-                        None => "None".to_string(),
-                        Some(table) => format!("Some({table})"),
-                    };
-                    format!(r##"SubField {{ datatype: r#"{datatype}"#, description: r#"{desc}"#, optionality: {opt}, repeatability: {rep}, max_length: {len}, table: {table} }}"##)
-                })
-                .collect::<Vec<String>>()
-                .join(", ");
-            fields.entry(
-                field_id,
-                &format!(
-                    r#"Field {{ description: "{}", subfields: &[{subfields}]}}"#,
-                    field.desc
-                ),
-            );
+        for (id, field) in version.fields.iter() {
+            fields.entry(id.as_str(), &format_field(&interner.fields.items[*field]));
         }
         let mut segments = Map::new();
-        for (segment_id, segment) in definitions.segments.iter() {
-            let fields = segment
-                .fields
-                .iter()
-                .map(|f| {
-                    let SubField { datatype, desc, opt, rep, len, table } = f;
-                    let opt = map_optionality(*opt);
-                    let rep = match rep {
-                        0 => "FieldRepeatability::Unbounded".to_string(),
-                        1 => "FieldRepeatability::Single".to_string(),
-                        n => format!("FieldRepeatability::Bounded({n})"),
-                    };
-                    let len = match len {
-                        Some(len) => format!("Some({len})"),
-                        None => "None".to_string(),
-                    };
-                    let table = match table {
-                        None => "None".to_string(),
-                        Some(table) => format!("Some({table})"),
-                    };
-                    format!(r##"SubField {{ datatype: r#"{datatype}"#, description: r#"{desc}"#, optionality: {opt}, repeatability: {rep}, max_length: {len}, table: {table} }}"##)
-                })
-                .collect::<Vec<String>>()
-                .join(", ");
+        for (id, segment) in version.segments.iter() {
             segments.entry(
-                segment_id,
-                &format!(
-                    r#"Segment {{ description: "{}", fields: &[{fields}]}}"#,
-                    segment.desc
-                ),
+                id.as_str(),
+                &format_segment(&interner.segments.items[*segment]),
             );
         }
         let mut messages = Map::new();
-        for (message_id, message) in definitions.messages.iter() {
-            let message_segments = message
-                .segments
-                .segments
-                .iter()
-                .map(format_message_segment)
-                .collect::<Vec<String>>()
-                .join(", ");
-
-            let Message { desc, name, .. } = message;
-            messages.entry(message_id, &format!(r##"Message {{ name: r#"{name}"#, description: r#"{desc}"#, segments: &[{message_segments}] }}"##));
+        for (id, name, desc, segments) in version.messages.iter() {
+            messages.entry(
+                id.as_str(),
+                &format!("Message {{ name: STR_{name}, description: STR_{desc}, segments: &MESSAGE_SEGMENTS_{segments} }}"),
+            );
         }
+
         writeln!(
             &mut out,
             "pub static DEFS_V{version_name}_FIELDS: phf::Map<&'static str, Field> = {};",
@@ -319,7 +1055,7 @@ fn codegen_definitions(mut out: BufWriter<File>) -> BufWriter<File> {
         .expect("can write to codegen.rs");
 
         definitions_map.entry(
-            version,
+            version.version.as_str(),
             &format!("Definition {{ fields: &DEFS_V{version_name}_FIELDS, segments: &DEFS_V{version_name}_SEGMENTS, messages: &DEFS_V{version_name}_MESSAGES }}"),
         );
     }
@@ -331,25 +1067,692 @@ fn codegen_definitions(mut out: BufWriter<File>) -> BufWriter<File> {
     )
     .expect("can write to codegen.rs");
 
+    out
+}
+
+#[cfg(feature = "compressed")]
+fn varint(buf: &mut Vec<u8>, mut n: usize) {
+    while n >= 0x80 {
+        buf.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+#[cfg(feature = "compressed")]
+fn optional(buf: &mut Vec<u8>, n: Option<usize>) {
+    varint(buf, n.map_or(0, |n| n + 1));
+}
+
+#[cfg(feature = "compressed")]
+fn string(buf: &mut Vec<u8>, s: &str) {
+    varint(buf, s.len());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+#[cfg(feature = "compressed")]
+fn list<T>(buf: &mut Vec<u8>, items: &[T], mut item: impl FnMut(&mut Vec<u8>, &T)) {
+    varint(buf, items.len());
+    for i in items {
+        item(buf, i);
+    }
+}
+
+/// Write the hash key and displacements of a `phf` map
+#[cfg(feature = "compressed")]
+fn hash_state(buf: &mut Vec<u8>, state: &phf_generator::HashState) {
+    buf.extend_from_slice(&state.key.to_le_bytes());
+    list(buf, &state.disps, |buf, (d1, d2)| {
+        varint(buf, *d1 as usize);
+        varint(buf, *d2 as usize);
+    });
+}
+
+/// Write a `phf` map with string keys, with its entries in hash order
+#[cfg(feature = "compressed")]
+fn map<T>(
+    buf: &mut Vec<u8>,
+    entries: &[T],
+    key: impl Fn(&T) -> &str,
+    mut entry: impl FnMut(&mut Vec<u8>, &T),
+) {
+    let keys = entries.iter().map(&key).collect::<Vec<&str>>();
+    let state = phf_generator::generate_hash(&keys);
+    hash_state(buf, &state);
+    varint(buf, state.map.len());
+    for index in state.map {
+        entry(buf, &entries[index]);
+    }
+}
+
+/// Write the interned definitions out as a deflated blob that is decoded the first time any
+/// definition is accessed; see `src/compressed.rs` for the decoder, which must be kept in sync
+/// with this. Sub-fields are linked to their tables by the decoder, so `_tables` isn't needed.
+#[cfg(feature = "compressed")]
+fn write_definitions(
+    mut out: BufWriter<File>,
+    interner: &Interner,
+    _tables: &HashSet<usize>,
+) -> BufWriter<File> {
+    let mut interner_keys = Pool::default();
+    let mut key = |s: &str| interner_keys.intern(s.to_string());
+    let versions = interner
+        .versions
+        .iter()
+        .map(|v| {
+            (
+                key(&v.version),
+                v.fields.iter().map(|f| key(&f.0)).collect::<Vec<_>>(),
+                v.segments.iter().map(|s| key(&s.0)).collect::<Vec<_>>(),
+                v.messages.iter().map(|m| key(&m.0)).collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let strings =
+        |buf: &mut Vec<u8>, strings: &[String]| list(buf, strings, |buf, s| string(buf, s));
+
+    let mut buf = Vec::new();
+    strings(&mut buf, &interner.strings.items);
+    strings(&mut buf, &interner_keys.items);
+    list(&mut buf, &interner.created, |buf, created| match *created {
+        Created::SubFields(i) => {
+            varint(buf, 0);
+            list(buf, &interner.subfields.items[i], |buf, s| {
+                varint(buf, s.datatype);
+                varint(buf, s.desc);
+                varint(buf, s.opt);
+                varint(buf, s.rep);
+                optional(buf, s.len);
+                optional(buf, s.conf_len);
+                varint(buf, s.truncate.map_or(0, |truncate| truncate as usize + 1));
+                optional(buf, s.table);
+                optional(buf, s.item);
+                optional(buf, s.link);
+            })
+        }
+        Created::Field(i) => {
+            let (desc, subfields) = interner.fields.items[i];
+            varint(buf, 1);
+            varint(buf, desc);
+            varint(buf, subfields);
+        }
+    });
+    list(&mut buf, &interner.segments.items, |buf, (desc, fields)| {
+        varint(buf, *desc);
+        varint(buf, *fields);
+    });
+    list(
+        &mut buf,
+        &interner.message_compounds.items,
+        |buf, compounds| {
+            list(buf, compounds, |buf, c| {
+                optional(buf, c.name);
+                varint(buf, c.desc);
+                varint(buf, c.min);
+                varint(buf, c.max);
+            })
+        },
+    );
+    list(
+        &mut buf,
+        &interner.message_segments.items,
+        |buf, segments| {
+            list(buf, segments, |buf, s| {
+                varint(buf, s.name);
+                varint(buf, s.desc);
+                varint(buf, s.min);
+                varint(buf, s.max);
+                optional(buf, s.children);
+                optional(buf, s.compounds);
+                optional(buf, s.link);
+            })
+        },
+    );
+    varint(&mut buf, interner.versions.len());
+    for (version, (version_key, field_keys, segment_keys, message_keys)) in
+        interner.versions.iter().zip(versions.iter())
+    {
+        varint(&mut buf, *version_key);
+        let fields = version.fields.iter().zip(field_keys).collect::<Vec<_>>();
+        map(
+            &mut buf,
+            &fields,
+            |(f, _)| &f.0,
+            |buf, ((_, field), key)| {
+                varint(buf, **key);
+                varint(buf, *field);
+            },
+        );
+        let segments = version
+            .segments
+            .iter()
+            .zip(segment_keys)
+            .collect::<Vec<_>>();
+        map(
+            &mut buf,
+            &segments,
+            |(s, _)| &s.0,
+            |buf, ((_, segment), key)| {
+                varint(buf, **key);
+                varint(buf, *segment);
+            },
+        );
+        let messages = version
+            .messages
+            .iter()
+            .zip(message_keys)
+            .collect::<Vec<_>>();
+        map(
+            &mut buf,
+            &messages,
+            |(m, _)| &m.0,
+            |buf, ((_, name, desc, segments), key)| {
+                varint(buf, **key);
+                varint(buf, *name);
+                varint(buf, *desc);
+                varint(buf, *segments);
+            },
+        );
+    }
+
+    let compressed = miniz_oxide::deflate::compress_to_vec(&buf, 10);
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join("definitions.bin");
+    std::fs::write(path, compressed).expect("can write definitions.bin");
+    writeln!(
+        &mut out,
+        r#"pub static DEFINITIONS_BLOB: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/definitions.bin"));"#
+    )
+    .expect("can write to codegen.rs");
+
+    out
+}
+
+fn codegen_definitions(out: BufWriter<File>, tables: &HashSet<usize>) -> BufWriter<File> {
+    let definitions =
+        std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/defs.json"))
+            .expect("can open ./assets/defs.json");
+    let mut definitions: HashMap<String, Definition> =
+        serde_json::from_str(&definitions).expect("can parse definitions");
+    apply_field_lengths(&mut definitions);
+    apply_item_numbers(&mut definitions);
+    let selection = MessageSelection::from_env();
+    let mut all_versions: Vec<String> = definitions.keys().cloned().collect();
+    all_versions.sort_by_key(|v| version_key(v));
+    let disabled_messages = disabled_messages(&definitions, &all_versions, &selection);
+    let (disabled_segments, disabled_datatypes) =
+        disabled_items(&definitions, &all_versions, &selection);
+    for definition in definitions.values_mut() {
+        prune_definition(definition, &selection);
+    }
+    let all_versions: Vec<&String> = all_versions.iter().collect();
+    let mut enabled_versions: Vec<&String> = Vec::with_capacity(all_versions.len());
+
+    let mut interner = Interner::default();
+    'versions: for version in all_versions.iter().copied() {
+        if std::env::var(format!("CARGO_FEATURE_{}", version.replace('.', ""))).is_err() {
+            p!("Version {version} feature disabled, version {version} will NOT be available!");
+            continue 'versions;
+        }
+        enabled_versions.push(version);
+
+        let version = interner.version(version, &definitions[version]);
+        interner.versions.push(version);
+    }
+
+    let mut out = write_definitions(out, &interner, tables);
+
+    writeln!(
+        &mut out,
+        "/// Messages left out by the message family features, with the feature that includes each\n/// and the [ALL_VERSIONS] that define it, as a bit set"
+    )
+    .expect("can write to codegen.rs");
+    writeln!(
+        &mut out,
+        "pub static DISABLED_MESSAGES: phf::Map<&'static str, (&'static str, u64)> = {};",
+        disabled_messages.build()
+    )
+    .expect("can write to codegen.rs");
+    writeln!(
+        &mut out,
+        "/// Segments left out by the message family features, with the first feature whose messages\n/// use each and the [ALL_VERSIONS] that define it, as a bit set"
+    )
+    .expect("can write to codegen.rs");
+    writeln!(
+        &mut out,
+        "pub static DISABLED_SEGMENTS: phf::Map<&'static str, (&'static str, u64)> = {};",
+        disabled_segments.build()
+    )
+    .expect("can write to codegen.rs");
+    writeln!(
+        &mut out,
+        "/// Datatypes left out by the message family features, with the first feature whose messages\n/// use each and the [ALL_VERSIONS] that define it, as a bit set"
+    )
+    .expect("can write to codegen.rs");
+    writeln!(
+        &mut out,
+        "pub static DISABLED_DATATYPES: phf::Map<&'static str, (&'static str, u64)> = {};",
+        disabled_datatypes.build()
+    )
+    .expect("can write to codegen.rs");
+
     writeln!(
         &mut out,
-        "/// All of the versions compiled into the library"
+        "/// All of the versions compiled into the library, oldest first"
     )
     .expect("can write to codegen.rs");
     writeln!(
         &mut out,
         "pub static VERSIONS: &[&str] = &[{}];",
-        definitions
-            .keys()
-            .map(|k| format!(r#""{k}""#))
-            .collect::<Vec<String>>()
-            .join(", ")
+        format_versions(&enabled_versions)
+    )
+    .expect("can write to codegen.rs");
+    writeln!(
+        &mut out,
+        "/// All of the versions known to the library, oldest first, whether or not their feature is enabled"
+    )
+    .expect("can write to codegen.rs");
+    writeln!(
+        &mut out,
+        "pub static ALL_VERSIONS: &[&str] = &[{}];",
+        format_versions(&all_versions)
     )
     .expect("can write to codegen.rs");
 
     out
 }
 
+/// Parse a conformance length in HL7 2.7 notation: the length, followed by `#` if the value
+/// may be truncated to that length or `=` if it may not
+fn parse_conformance_length(notation: &str) -> Option<(usize, Option<bool>)> {
+    let (len, truncate) = match notation.as_bytes().last()? {
+        b'#' => (&notation[..notation.len() - 1], Some(true)),
+        b'=' => (&notation[..notation.len() - 1], Some(false)),
+        _ => (notation, None),
+    };
+    Some((len.parse().ok()?, truncate))
+}
+
+/// Apply the conformance lengths from `field_lengths.json`, which are keyed by version and then
+/// by `SEG-n` (segment fields) or `DT.n` (datatype components)
+fn apply_field_lengths(definitions: &mut HashMap<String, Definition>) {
+    let field_lengths = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/assets/field_lengths.json"
+    ))
+    .expect("can open ./assets/field_lengths.json");
+    let field_lengths: HashMap<String, HashMap<String, String>> =
+        serde_json::from_str(&field_lengths).expect("can parse field lengths");
+
+    for (version, lengths) in field_lengths {
+        // a typo in the asset would otherwise silently do nothing
+        let definition = definitions
+            .get_mut(&version)
+            .unwrap_or_else(|| panic!("field lengths given for unknown version {version}"));
+        for (path, notation) in lengths {
+            let (conf_len, truncate) = parse_conformance_length(&notation)
+                .unwrap_or_else(|| panic!("invalid conformance length {notation:?} for {path}"));
+            let subfield = match path.split_once(['-', '.']) {
+                Some((segment, n)) if path.as_bytes()[segment.len()] == b'-' => definition
+                    .segments
+                    .get_mut(segment)
+                    .zip(n.parse::<usize>().ok())
+                    .and_then(|(segment, n)| segment.fields.get_mut(n.checked_sub(1)?)),
+                Some((datatype, n)) => definition
+                    .fields
+                    .get_mut(datatype)
+                    .zip(n.parse::<usize>().ok())
+                    .and_then(|(field, n)| field.subfields.get_mut(n.checked_sub(1)?)),
+                None => None,
+            };
+            let subfield = subfield.unwrap_or_else(|| {
+                panic!("field lengths given for unknown item {path} in version {version}")
+            });
+            subfield.conf_len = Some(conf_len);
+            subfield.truncate = truncate;
+        }
+    }
+}
+
+/// An entry in `item_numbers.json`: the item number, and the description of the field it
+/// belongs to
+#[derive(Deserialize)]
+struct ItemNumber {
+    item: usize,
+    description: String,
+}
+
+/// Whether two field descriptions name the same field, ignoring case and punctuation (the
+/// source data isn't consistent about either between versions)
+fn same_description(a: &str, b: &str) -> bool {
+    let normalise = |s: &str| {
+        s.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+            .collect::<String>()
+    };
+    normalise(a) == normalise(b)
+}
+
+/// Apply the item numbers from `item_numbers.json`, which are keyed by version (or `*` for
+/// every version) and then by `SEG-n`. Each entry names the field it belongs to, and is only
+/// applied where the field's description matches, so that an entry can't land on a different
+/// field in a version where the segment's fields have moved. Entries for a specific version take
+/// precedence, and entries for every version are skipped where the segment doesn't have the
+/// field.
+fn apply_item_numbers(definitions: &mut HashMap<String, Definition>) {
+    let item_numbers = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/assets/item_numbers.json"
+    ))
+    .expect("can open ./assets/item_numbers.json");
+    let mut item_numbers: HashMap<String, HashMap<String, ItemNumber>> =
+        serde_json::from_str(&item_numbers).expect("can parse item numbers");
+    let every_version = item_numbers.remove("*").unwrap_or_default();
+    for version in item_numbers.keys() {
+        if !definitions.contains_key(version) {
+            panic!("item numbers given for unknown version {version}");
+        }
+    }
+
+    let mut mismatched: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut versions = definitions.keys().cloned().collect::<Vec<_>>();
+    versions.sort();
+    for version in versions.iter() {
+        let definition = definitions.get_mut(version).expect("version exists");
+        let specific = item_numbers.get(version);
+        let items = every_version
+            .iter()
+            .map(|(path, item)| (path, item, false))
+            .chain(
+                specific
+                    .into_iter()
+                    .flatten()
+                    .map(|(path, item)| (path, item, true)),
+            );
+        for (path, item, specific) in items {
+            let subfield = path
+                .split_once('-')
+                .and_then(|(segment, n)| Some((segment, n.parse::<usize>().ok()?)))
+                .and_then(|(segment, n)| {
+                    definition
+                        .segments
+                        .get_mut(segment)?
+                        .fields
+                        .get_mut(n.checked_sub(1)?)
+                });
+            match subfield {
+                Some(subfield) if !same_description(&subfield.desc, &item.description) => {
+                    if specific {
+                        panic!(
+                            "item number for {path} in version {version} is for {:?}, but the field is {:?}",
+                            item.description, subfield.desc
+                        );
+                    }
+                    mismatched.entry(path).or_default().push(version);
+                }
+                Some(subfield) if specific || subfield.item.is_none() => {
+                    subfield.item = Some(item.item)
+                }
+                Some(_) => {}
+                None if specific => {
+                    panic!("item number given for unknown item {path} in version {version}")
+                }
+                None => {}
+            }
+        }
+    }
+
+    let mut mismatched = mismatched.into_iter().collect::<Vec<_>>();
+    mismatched.sort();
+    for (path, versions) in mismatched {
+        p!(
+            "Item number for {path} ({:?}) not applied in versions {} where the field is described differently",
+            every_version[path].description,
+            versions.join(", ")
+        );
+    }
+}
+
+/// Cargo features which select families of messages, along with the message types in each
+const MESSAGE_FAMILIES: &[(&str, &[&str])] = &[
+    ("adt", &["ADT"]),
+    ("bar", &["BAR"]),
+    ("dft", &["DFT"]),
+    ("mdm", &["MDM"]),
+    ("mfn", &["MFN", "MFK"]),
+    ("orm", &["ORM", "ORR", "OML", "ORL"]),
+    ("oru", &["ORU", "OUL"]),
+    ("qry", &["QRY", "QBP", "RSP", "DSR"]),
+    (
+        "rde",
+        &["RDE", "RRE", "RDS", "RRD", "RGV", "RRG", "RAS", "RRA"],
+    ),
+    ("siu", &["SIU", "SRM", "SRR"]),
+    ("vxu", &["VXU", "VXQ", "VXR", "VXX"]),
+];
+
+/// The general acknowledgment is a response to every family, so it is always included
+const ALWAYS_INCLUDED_MESSAGES: &[&str] = &["ACK"];
+
+/// Which messages to generate definitions for
+enum MessageSelection {
+    All,
+    /// Message IDs (`ADT_A01`) or message types (`ADT`) to include
+    Only(Vec<String>),
+}
+
+impl MessageSelection {
+    fn from_env() -> MessageSelection {
+        println!("cargo:rerun-if-env-changed=HL7_DEFINITIONS_MESSAGES");
+        let allow_list = std::env::var("HL7_DEFINITIONS_MESSAGES").ok();
+
+        if std::env::var("CARGO_FEATURE_ALL_MESSAGES").is_ok() {
+            if allow_list.is_some() {
+                p!("HL7_DEFINITIONS_MESSAGES is ignored because the all-messages feature is enabled");
+            }
+            return MessageSelection::All;
+        }
+
+        let mut messages: Vec<String> = ALWAYS_INCLUDED_MESSAGES
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+        for (feature, types) in MESSAGE_FAMILIES {
+            if std::env::var(format!("CARGO_FEATURE_{}", feature.to_uppercase())).is_ok() {
+                messages.extend(types.iter().map(|t| t.to_string()));
+            }
+        }
+        if let Some(allow_list) = allow_list {
+            messages.extend(
+                allow_list
+                    .split(',')
+                    .map(|m| m.trim().to_uppercase())
+                    .filter(|m| !m.is_empty()),
+            );
+        }
+        p!(
+            "Only generating definitions for messages: {}",
+            messages.join(", ")
+        );
+        MessageSelection::Only(messages)
+    }
+
+    fn includes(&self, message_id: &str) -> bool {
+        match self {
+            MessageSelection::All => true,
+            MessageSelection::Only(messages) => {
+                let message_type = message_id.split('_').next().unwrap_or(message_id);
+                messages
+                    .iter()
+                    .any(|m| m == message_id || m == message_type)
+            }
+        }
+    }
+}
+
+/// The messages that the selection leaves out, mapped to the feature that would include each
+/// and the versions that define it, as a bit set of indices into `all_versions`
+fn disabled_messages(
+    definitions: &HashMap<String, Definition>,
+    all_versions: &[String],
+    selection: &MessageSelection,
+) -> Map<String> {
+    let mut disabled: HashMap<&str, u64> = HashMap::new();
+    for (i, version) in all_versions.iter().enumerate() {
+        for id in definitions[version].messages.keys() {
+            if !selection.includes(id) {
+                *disabled.entry(id).or_default() |= 1 << i;
+            }
+        }
+    }
+
+    let mut map = Map::new();
+    for (id, versions) in disabled {
+        let feature = message_feature(id);
+        map.entry(id.to_string(), &format!("(\"{feature}\", {versions})"));
+    }
+    map
+}
+
+/// The feature that includes a message, by its message type
+fn message_feature(id: &str) -> &'static str {
+    let message_type = id.split('_').next().unwrap_or(id);
+    MESSAGE_FAMILIES
+        .iter()
+        .find(|(_, types)| types.contains(&message_type))
+        .map_or("all-messages", |(feature, _)| feature)
+}
+
+/// The segments and datatypes used by the messages that `include` accepts
+fn reachable(
+    definition: &Definition,
+    include: impl Fn(&str) -> bool,
+) -> (HashSet<&str>, HashSet<&str>) {
+    fn segment_names<'a>(segments: &'a [MessageSegment], names: &mut HashSet<&'a str>) {
+        for segment in segments {
+            names.insert(&segment.name);
+            if let Some(children) = &segment.children {
+                segment_names(children, names);
+            }
+            for compound in segment.compounds.iter().flatten() {
+                if let Some(name) = &compound.name {
+                    names.insert(name);
+                }
+            }
+        }
+    }
+
+    let mut segments = HashSet::new();
+    for (_, message) in definition.messages.iter().filter(|(id, _)| include(id)) {
+        segment_names(&message.segments.segments, &mut segments);
+    }
+
+    let mut datatypes: HashSet<&str> = HashSet::new();
+    let mut pending: Vec<&str> = segments
+        .iter()
+        .filter_map(|id| definition.segments.get(*id))
+        .flat_map(|segment| segment.fields.iter().map(|f| f.datatype.as_str()))
+        .collect();
+    while let Some(datatype) = pending.pop() {
+        if !datatypes.insert(datatype) {
+            continue;
+        }
+        if let Some(field) = definition.fields.get(datatype) {
+            pending.extend(field.subfields.iter().map(|s| s.datatype.as_str()));
+        }
+    }
+    (segments, datatypes)
+}
+
+/// The segments and datatypes that pruning leaves out, each mapped to the first message family
+/// feature whose messages use it and the versions that define it, as a bit set of indices into
+/// `all_versions`
+fn disabled_items(
+    definitions: &HashMap<String, Definition>,
+    all_versions: &[String],
+    selection: &MessageSelection,
+) -> (Map<String>, Map<String>) {
+    let mut segments: HashMap<&str, (&str, u64)> = HashMap::new();
+    let mut datatypes: HashMap<&str, (&str, u64)> = HashMap::new();
+    if !matches!(selection, MessageSelection::All) {
+        for (i, version) in all_versions.iter().enumerate() {
+            let definition = &definitions[version];
+            let (kept_segments, kept_datatypes) =
+                reachable(definition, |id| selection.includes(id));
+            let families = MESSAGE_FAMILIES
+                .iter()
+                .map(|(feature, _)| {
+                    let used = reachable(definition, |id| message_feature(id) == *feature);
+                    (*feature, used)
+                })
+                .collect::<Vec<_>>();
+
+            for id in definition.segments.keys() {
+                if !kept_segments.contains(id.as_str()) {
+                    let feature = families
+                        .iter()
+                        .find(|(_, (used, _))| used.contains(id.as_str()))
+                        .map_or("all-messages", |(feature, _)| feature);
+                    segments.entry(id).or_insert((feature, 0)).1 |= 1 << i;
+                }
+            }
+            for id in definition.fields.keys() {
+                if !kept_datatypes.contains(id.as_str()) {
+                    let feature = families
+                        .iter()
+                        .find(|(_, (_, used))| used.contains(id.as_str()))
+                        .map_or("all-messages", |(feature, _)| feature);
+                    datatypes.entry(id).or_insert((feature, 0)).1 |= 1 << i;
+                }
+            }
+        }
+    }
+
+    let map = |items: HashMap<&str, (&str, u64)>| {
+        let mut map = Map::new();
+        for (id, (feature, versions)) in items {
+            map.entry(id.to_string(), &format!("(\"{feature}\", {versions})"));
+        }
+        map
+    };
+    (map(segments), map(datatypes))
+}
+
+/// Remove the messages that weren't selected, along with any segments and datatypes that are no
+/// longer reachable from the messages that remain
+fn prune_definition(definition: &mut Definition, selection: &MessageSelection) {
+    if matches!(selection, MessageSelection::All) {
+        return;
+    }
+
+    let (segments, datatypes) = reachable(definition, |id| selection.includes(id));
+    let segments: HashSet<String> = segments.into_iter().map(String::from).collect();
+    let datatypes: HashSet<String> = datatypes.into_iter().map(String::from).collect();
+    definition.messages.retain(|id, _| selection.includes(id));
+    definition.segments.retain(|id, _| segments.contains(id));
+    definition.fields.retain(|id, _| datatypes.contains(id));
+}
+
+/// Sort key for version strings so that `2.10` comes after `2.9`
+fn version_key(version: &str) -> Vec<u32> {
+    version
+        .split('.')
+        .map(|part| {
+            part.parse::<u32>()
+                .unwrap_or_else(|_| panic!("can parse version {version}"))
+        })
+        .collect()
+}
+
+fn format_versions(versions: &[&String]) -> String {
+    versions
+        .iter()
+        .map(|v| format!(r#""{v}""#))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(not(feature = "compressed"))]
 fn map_optionality(opt: usize) -> &'static str {
     match opt {
         1 => "FieldOptionality::Optional",
@@ -360,11 +1763,15 @@ fn map_optionality(opt: usize) -> &'static str {
 }
 
 fn main() {
+    // the env vars the build reads are declared where they're read, which turns off cargo's
+    // default of rerunning on any change, so the inputs have to be declared too
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=assets");
     let path = Path::new(&env::var("OUT_DIR").unwrap()).join("codegen.rs");
     let mut out = BufWriter::new(File::create(path).unwrap());
 
-    writeln!(&mut out, "#[allow(unused)]\npub mod codegen {{\nuse super::*;\npub type TableValues = phf::Map<&'static str, &'static str>;").expect("can write to codegen.rs");
-    let out = codegen_tables(out);
-    let mut out = codegen_definitions(out);
+    writeln!(&mut out, "#[allow(unused)]\npub mod codegen {{\nuse super::*;\npub type TableValues = phf::Map<&'static str, TableValue>;").expect("can write to codegen.rs");
+    let (out, tables) = codegen_tables(out);
+    let mut out = codegen_definitions(out, &tables);
     writeln!(&mut out, "}}").expect("can write to codegen.rs");
 }
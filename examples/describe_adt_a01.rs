@@ -1,11 +1,44 @@
 use hl7_definitions::*;
 
-fn print_fields(name: &str, segment: &Segment) {
-    for (i, field) in segment.fields.iter().enumerate() {
-        let i = i + 1;
+struct Describe;
+
+impl MessageVisitor for Describe {
+    fn enter_group(&mut self, group: &'static MessageSegment, context: &VisitContext<'_>) {
+        let indent = "  ".repeat(context.depth + 1);
+        println!("{indent}{} group ({}):", group.name, group.description);
+    }
+
+    fn visit_segment(
+        &mut self,
+        segment: &'static MessageSegment,
+        _definition: Option<&'static Segment>,
+        context: &VisitContext<'_>,
+    ) {
+        let indent = "  ".repeat(context.depth + 1);
+        print!("{indent}{}", segment.name);
+        if context.is_required() {
+            print!(" (required)");
+        }
+        if segment.max > 1 {
+            print!(" (repeatable)");
+        }
+        if segment.is_choice() {
+            let alternatives = segment.alternatives().collect::<Vec<_>>();
+            print!(" (one of {})", alternatives.join(" | "));
+        }
+        println!(":");
+    }
+
+    fn visit_field(&mut self, field: &'static SubField, context: &VisitContext<'_>) {
+        let indent = "  ".repeat(context.depth + 1);
         print!(
-            "    {name}.{i} - {} [{}] {}, {}",
-            field.description, field.datatype, field.optionality, field.repeatability
+            "{indent}{}.{} - {} [{}] {}, {}",
+            context.segment.unwrap_or_default(),
+            context.field.unwrap_or_default(),
+            field.description,
+            field.datatype,
+            field.optionality,
+            field.repeatability
         );
         if let Some(table) = field.table {
             print!(" (table {table:>0})");
@@ -14,28 +47,8 @@ fn print_fields(name: &str, segment: &Segment) {
     }
 }
 
-fn print_message_segment(segment: &MessageSegment) {
-    print!("  {}", segment.name);
-    if segment.min > 0 {
-        print!(" (required)");
-    }
-    if segment.max > 1 {
-        print!(" (repeatable)");
-    }
-    println!(":");
-
-    match get_segment("2.3", segment.name) {
-        Some(seg) => print_fields(segment.name, seg),
-        None => {
-            if let Some(children) = segment.children {
-                children.iter().for_each(print_message_segment);
-            }
-        }
-    }
-}
-
 pub fn main() {
     let a01 = get_message("2.3", "ADT_A01").expect("Can get A01 message");
     println!("{} ({}) segments:", a01.name, a01.description);
-    a01.segments.iter().for_each(print_message_segment)
+    walk_message("2.3", a01, &mut Describe);
 }
@@ -0,0 +1,417 @@
+#ifndef HL7_DEFINITIONS_H
+#define HL7_DEFINITIONS_H
+
+/* Generated with cbindgen from src/ffi.rs; do not edit by hand. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+// How "required" a field is; see [FieldOptionality]
+typedef enum Hl7Optionality {
+  HL7_OPTIONALITY_OPTIONAL,
+  HL7_OPTIONALITY_REQUIRED,
+  HL7_OPTIONALITY_CONDITIONAL,
+  HL7_OPTIONALITY_BACKWARD_COMPATIBILITY,
+} Hl7Optionality;
+
+// How many times a field can be repeated; see [FieldRepeatability]
+typedef enum Hl7Repeatability {
+  HL7_REPEATABILITY_UNBOUNDED,
+  HL7_REPEATABILITY_SINGLE,
+  HL7_REPEATABILITY_BOUNDED,
+} Hl7Repeatability;
+
+// The root definition for a given version, describing the schema for that HL7 version
+typedef struct Hl7Definition Hl7Definition;
+
+// A field type (could be an HL7 field, component, or sub-component depending on its usage),
+// effectively a datatype
+typedef struct Hl7Field Hl7Field;
+
+// Schema for a mesasge (`ADT_A01`, etc)
+typedef struct Hl7Message Hl7Message;
+
+// One of the alternatives for a choice position in a message; see
+// [MessageSegment::compounds]
+typedef struct Hl7MessageCompound Hl7MessageCompound;
+
+// A segment within a message
+typedef struct Hl7MessageSegment Hl7MessageSegment;
+
+// Schema for a segment (`MSH`, `PID`, etc)
+typedef struct Hl7Segment Hl7Segment;
+
+// Generally the lowest-level datatype, represents what a component or sub-component can be
+typedef struct Hl7SubField Hl7SubField;
+
+// A borrowed UTF-8 string which is _not_ NUL-terminated
+typedef struct Hl7Str {
+  // Pointer to the first byte of the string, or null if there is no string
+  const char *ptr;
+  // The length of the string in bytes
+  size_t len;
+} Hl7Str;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// The number of versions compiled into the library
+size_t hl7_version_count(void);
+
+// The version at `index` (oldest first), or a null string if `index` is out of range
+struct Hl7Str hl7_version_at(size_t index);
+
+// Get the definition for a version, or null if the version isn't compiled in
+//
+// # Safety
+//
+// `version` must be null or a valid NUL-terminated string.
+const struct Hl7Definition *hl7_definition(const char *version);
+
+// Get a field from a definition by its ID, or null if it doesn't exist
+//
+// # Safety
+//
+// `definition` must be null or a pointer returned by [hl7_definition], and `id` must be null
+// or a valid NUL-terminated string.
+const struct Hl7Field *hl7_definition_field(const struct Hl7Definition *definition, const char *id);
+
+// The number of fields in a definition
+//
+// # Safety
+//
+// `definition` must be null or a pointer returned by [hl7_definition].
+size_t hl7_definition_field_count(const struct Hl7Definition *definition);
+
+// Get the field at `index` in a definition (in no particular order), writing its ID to `id`
+// if `id` isn't null. Returns null if `index` is out of range.
+//
+// # Safety
+//
+// `definition` must be null or a pointer returned by [hl7_definition], and `id` must be null
+// or valid for writes.
+const struct Hl7Field *hl7_definition_field_at(const struct Hl7Definition *definition,
+                                               size_t index,
+                                               struct Hl7Str *id);
+
+// Get a segment from a definition by its ID, or null if it doesn't exist
+//
+// # Safety
+//
+// `definition` must be null or a pointer returned by [hl7_definition], and `id` must be null
+// or a valid NUL-terminated string.
+const struct Hl7Segment *hl7_definition_segment(const struct Hl7Definition *definition,
+                                                const char *id);
+
+// The number of segments in a definition
+//
+// # Safety
+//
+// `definition` must be null or a pointer returned by [hl7_definition].
+size_t hl7_definition_segment_count(const struct Hl7Definition *definition);
+
+// Get the segment at `index` in a definition (in no particular order), writing its ID to `id`
+// if `id` isn't null. Returns null if `index` is out of range.
+//
+// # Safety
+//
+// `definition` must be null or a pointer returned by [hl7_definition], and `id` must be null
+// or valid for writes.
+const struct Hl7Segment *hl7_definition_segment_at(const struct Hl7Definition *definition,
+                                                   size_t index,
+                                                   struct Hl7Str *id);
+
+// Get a message from a definition by its ID, or null if it doesn't exist
+//
+// # Safety
+//
+// `definition` must be null or a pointer returned by [hl7_definition], and `id` must be null
+// or a valid NUL-terminated string.
+const struct Hl7Message *hl7_definition_message(const struct Hl7Definition *definition,
+                                                const char *id);
+
+// The number of messages in a definition
+//
+// # Safety
+//
+// `definition` must be null or a pointer returned by [hl7_definition].
+size_t hl7_definition_message_count(const struct Hl7Definition *definition);
+
+// Get the message at `index` in a definition (in no particular order), writing its ID to `id`
+// if `id` isn't null. Returns null if `index` is out of range.
+//
+// # Safety
+//
+// `definition` must be null or a pointer returned by [hl7_definition], and `id` must be null
+// or valid for writes.
+const struct Hl7Message *hl7_definition_message_at(const struct Hl7Definition *definition,
+                                                   size_t index,
+                                                   struct Hl7Str *id);
+
+// The description of a field
+//
+// # Safety
+//
+// `field` must be null or a pointer handed out by this library.
+struct Hl7Str hl7_field_description(const struct Hl7Field *field);
+
+// The number of sub-fields (components) of a field
+//
+// # Safety
+//
+// `field` must be null or a pointer handed out by this library.
+size_t hl7_field_subfield_count(const struct Hl7Field *field);
+
+// Get one of the sub-fields (components) of a field, or null if `index` is out of range
+//
+// # Safety
+//
+// `field` must be null or a pointer handed out by this library.
+const struct Hl7SubField *hl7_field_subfield_at(const struct Hl7Field *field, size_t index);
+
+// The description of a segment
+//
+// # Safety
+//
+// `segment` must be null or a pointer handed out by this library.
+struct Hl7Str hl7_segment_description(const struct Hl7Segment *segment);
+
+// The number of fields of a segment
+//
+// # Safety
+//
+// `segment` must be null or a pointer handed out by this library.
+size_t hl7_segment_field_count(const struct Hl7Segment *segment);
+
+// Get one of the fields of a segment, or null if `index` is out of range
+//
+// # Safety
+//
+// `segment` must be null or a pointer handed out by this library.
+const struct Hl7SubField *hl7_segment_field_at(const struct Hl7Segment *segment, size_t index);
+
+// The datatype of a sub-field
+//
+// # Safety
+//
+// `subfield` must be null or a pointer handed out by this library.
+struct Hl7Str hl7_subfield_datatype(const struct Hl7SubField *subfield);
+
+// The description of a sub-field
+//
+// # Safety
+//
+// `subfield` must be null or a pointer handed out by this library.
+struct Hl7Str hl7_subfield_description(const struct Hl7SubField *subfield);
+
+// Whether a sub-field is required
+//
+// # Safety
+//
+// `subfield` must be null or a pointer handed out by this library.
+enum Hl7Optionality hl7_subfield_optionality(const struct Hl7SubField *subfield);
+
+// How many times a sub-field can be repeated. If the repeatability is
+// [Hl7Repeatability::Bounded], the maximum number of repetitions is written to `max`.
+//
+// # Safety
+//
+// `subfield` must be null or a pointer handed out by this library, and `max` must be null or
+// valid for writes.
+enum Hl7Repeatability hl7_subfield_repeatability(const struct Hl7SubField *subfield, size_t *max);
+
+// Write the maximum length of a sub-field to `max_length`, returning `false` if it is
+// unbounded or not applicable
+//
+// # Safety
+//
+// `subfield` must be null or a pointer handed out by this library, and `max_length` must be
+// null or valid for writes.
+bool hl7_subfield_max_length(const struct Hl7SubField *subfield, size_t *max_length);
+
+// Write the conformance length of a sub-field to `conformance_length`, returning `false` if
+// it isn't specified
+//
+// # Safety
+//
+// `subfield` must be null or a pointer handed out by this library, and `conformance_length`
+// must be null or valid for writes.
+bool hl7_subfield_conformance_length(const struct Hl7SubField *subfield,
+                                     size_t *conformance_length);
+
+// Whether values of a sub-field longer than its conformance length may be truncated to it
+//
+// # Safety
+//
+// `subfield` must be null or a pointer handed out by this library.
+bool hl7_subfield_may_truncate(const struct Hl7SubField *subfield);
+
+// Write the item number of a sub-field's data element to `item_number`, returning `false` if
+// it isn't known
+//
+// # Safety
+//
+// `subfield` must be null or a pointer handed out by this library, and `item_number` must be
+// null or valid for writes.
+bool hl7_subfield_item_number(const struct Hl7SubField *subfield, size_t *item_number);
+
+// Write the number of the table holding the sub-field's valid values to `table`, returning
+// `false` if the sub-field isn't coded
+//
+// # Safety
+//
+// `subfield` must be null or a pointer handed out by this library, and `table` must be null
+// or valid for writes.
+bool hl7_subfield_table(const struct Hl7SubField *subfield, uint16_t *table);
+
+// The name of a message
+//
+// # Safety
+//
+// `message` must be null or a pointer handed out by this library.
+struct Hl7Str hl7_message_name(const struct Hl7Message *message);
+
+// The description of a message
+//
+// # Safety
+//
+// `message` must be null or a pointer handed out by this library.
+struct Hl7Str hl7_message_description(const struct Hl7Message *message);
+
+// The number of top-level segments of a message
+//
+// # Safety
+//
+// `message` must be null or a pointer handed out by this library.
+size_t hl7_message_segment_count(const struct Hl7Message *message);
+
+// Get one of the top-level segments of a message, or null if `index` is out of range
+//
+// # Safety
+//
+// `message` must be null or a pointer handed out by this library.
+const struct Hl7MessageSegment *hl7_message_segment_at(const struct Hl7Message *message,
+                                                       size_t index);
+
+// The name (segment ID) of a segment within a message
+//
+// # Safety
+//
+// `segment` must be null or a pointer handed out by this library.
+struct Hl7Str hl7_message_segment_name(const struct Hl7MessageSegment *segment);
+
+// The description of a segment within a message
+//
+// # Safety
+//
+// `segment` must be null or a pointer handed out by this library.
+struct Hl7Str hl7_message_segment_description(const struct Hl7MessageSegment *segment);
+
+// The minimum number of times a segment must appear in a message
+//
+// # Safety
+//
+// `segment` must be null or a pointer handed out by this library.
+size_t hl7_message_segment_min(const struct Hl7MessageSegment *segment);
+
+// The maximum number of times a segment may appear in a message
+//
+// # Safety
+//
+// `segment` must be null or a pointer handed out by this library.
+size_t hl7_message_segment_max(const struct Hl7MessageSegment *segment);
+
+// The number of child segments of a segment within a message
+//
+// # Safety
+//
+// `segment` must be null or a pointer handed out by this library.
+size_t hl7_message_segment_child_count(const struct Hl7MessageSegment *segment);
+
+// Get one of the child segments of a segment within a message, or null if `index` is out of range
+//
+// # Safety
+//
+// `segment` must be null or a pointer handed out by this library.
+const struct Hl7MessageSegment *hl7_message_segment_child_at(const struct Hl7MessageSegment *segment,
+                                                             size_t index);
+
+// The number of choices (compounds) of a segment within a message
+//
+// # Safety
+//
+// `segment` must be null or a pointer handed out by this library.
+size_t hl7_message_segment_compound_count(const struct Hl7MessageSegment *segment);
+
+// Get one of the choices (compounds) of a segment within a message, or null if `index` is out of range
+//
+// # Safety
+//
+// `segment` must be null or a pointer handed out by this library.
+const struct Hl7MessageCompound *hl7_message_segment_compound_at(const struct Hl7MessageSegment *segment,
+                                                                 size_t index);
+
+// The name of a compound, or a null string if it has none
+//
+// # Safety
+//
+// `compound` must be null or a pointer handed out by this library.
+struct Hl7Str hl7_message_compound_name(const struct Hl7MessageCompound *compound);
+
+// The description of a compound
+//
+// # Safety
+//
+// `compound` must be null or a pointer handed out by this library.
+struct Hl7Str hl7_message_compound_description(const struct Hl7MessageCompound *compound);
+
+// The minimum number of times a compound must appear
+//
+// # Safety
+//
+// `compound` must be null or a pointer handed out by this library.
+size_t hl7_message_compound_min(const struct Hl7MessageCompound *compound);
+
+// The maximum number of times a compound may appear
+//
+// # Safety
+//
+// `compound` must be null or a pointer handed out by this library.
+size_t hl7_message_compound_max(const struct Hl7MessageCompound *compound);
+
+// Resolve a path such as `PID-5.1` in the given version, returning the most specific
+// sub-field it refers to, or null if it can't be resolved
+//
+// # Safety
+//
+// `version` and `path` must be null or valid NUL-terminated strings.
+const struct Hl7SubField *hl7_resolve_path(const char *version, const char *path);
+
+// The description of a table, or a null string if the table doesn't exist
+struct Hl7Str hl7_table_description(uint16_t table);
+
+// The description of a value in a table, or a null string if it doesn't exist
+//
+// # Safety
+//
+// `code` must be null or a valid NUL-terminated string.
+struct Hl7Str hl7_table_value(uint16_t table, const char *code);
+
+// The number of values in a table
+size_t hl7_table_value_count(uint16_t table);
+
+// Get the description of the value at `index` in a table (in no particular order), writing
+// its code to `code` if `code` isn't null. Returns a null string if `index` is out of range.
+//
+// # Safety
+//
+// `code` must be null or valid for writes.
+struct Hl7Str hl7_table_value_at(uint16_t table, size_t index, struct Hl7Str *code);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* HL7_DEFINITIONS_H */
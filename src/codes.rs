@@ -0,0 +1,62 @@
+//! Enums generated from the HL7 tables, so that codes can be matched on instead of compared as
+//! strings
+//!
+//! By default an enum is generated for every HL7-defined table; set `HL7_DEFINITIONS_TABLE_ENUMS`
+//! to a comma-separated list of table numbers at build time to choose the tables instead (`hl7`
+//! stands for every HL7-defined table, so `hl7,1,4` adds tables 0001 and 0004).
+//!
+//! Enums and variants are named from the table and value descriptions, unless
+//! `assets/table_enum_names.json` gives them a name. User-defined tables are routinely extended
+//! with local codes, so their enums have an `Other(String)` variant and parsing them never
+//! fails; parsing a code that isn't in an HL7-defined table is an [UnknownCode] error.
+
+use core::fmt::Display;
+
+use crate::TableId;
+
+/// The error returned when parsing a code that isn't in an HL7-defined table
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct UnknownCode {
+    /// The table the code was looked up in
+    pub table: TableId,
+}
+
+impl Display for UnknownCode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "code is not in HL7 table {}", self.table)
+    }
+}
+
+impl core::error::Error for UnknownCode {}
+
+include!(concat!(env!("OUT_DIR"), "/table_enums.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn can_parse_hl7_table_codes() {
+        let code: AcknowledgmentCode = "AE".parse().expect("AE is an acknowledgment code");
+        assert_eq!(code, AcknowledgmentCode::ApplicationError);
+        assert_eq!(code.as_code(), "AE");
+        assert_eq!(code.to_string(), "AE");
+        assert!(code.description().contains("Application Error"));
+        assert_eq!(
+            "ZZ".parse::<AcknowledgmentCode>(),
+            Err(UnknownCode { table: TableId(8) })
+        );
+        assert_eq!(AcknowledgmentCode::TABLE, TableId(8));
+    }
+
+    #[test]
+    fn every_value_round_trips() {
+        let table = crate::get_table(ProcessingId::TABLE).expect("table 0103 exists");
+        for (code, description) in table.iter() {
+            let value: ProcessingId = code.parse().expect("codes in the table parse");
+            assert_eq!(value.as_code(), code);
+            assert_eq!(value.description(), description);
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! Decoders for the definitions and tables blobs that `build.rs` writes when the `compressed`
+//! feature is enabled. Each blob is inflated and decoded the first time anything in it is
+//! accessed, and the decoded data lives for the rest of the program.
+//!
+//! The layouts must be kept in sync with `write_definitions` and `write_tables` in `build.rs`.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use phf::Map;
+
+use crate::codegen::{self, TableValues};
+use crate::{
+    get_table, Definition, Field, FieldOptionality, FieldRepeatability, Link, Message,
+    MessageCompound, MessageSegment, Segment, SubField, Table, TableValue, Truncation, ValueStatus,
+};
+
+static DEFINITIONS: OnceLock<Vec<(&'static str, &'static Definition)>> = OnceLock::new();
+static TABLES: OnceLock<&'static Map<u16, &'static TableValues>> = OnceLock::new();
+
+/// Get the definition for a version, decoding all definitions if this is the first access
+pub(crate) fn definition(version: &str) -> Option<&'static Definition> {
+    DEFINITIONS
+        .get_or_init(decode)
+        .iter()
+        .find(|(v, _)| *v == version)
+        .map(|(_, definition)| *definition)
+}
+
+/// Get the values of every table, decoding them if this is the first access
+pub(crate) fn tables() -> &'static Map<u16, &'static TableValues> {
+    TABLES.get_or_init(decode_tables)
+}
+
+fn leak<T>(items: Vec<T>) -> &'static [T] {
+    Box::leak(items.into_boxed_slice())
+}
+
+struct Reader {
+    buf: &'static [u8],
+    pos: usize,
+}
+
+impl Reader {
+    fn inflate(blob: &[u8]) -> Reader {
+        let buf = miniz_oxide::inflate::decompress_to_vec(blob).expect("can inflate blob");
+        Reader {
+            buf: leak(buf),
+            pos: 0,
+        }
+    }
+
+    fn byte(&mut self) -> u8 {
+        let b = self.buf[self.pos];
+        self.pos += 1;
+        b
+    }
+
+    fn varint(&mut self) -> usize {
+        let mut n = 0;
+        let mut shift = 0;
+        loop {
+            let b = self.byte();
+            n |= ((b & 0x7f) as usize) << shift;
+            if b & 0x80 == 0 {
+                return n;
+            }
+            shift += 7;
+        }
+    }
+
+    fn optional(&mut self) -> Option<usize> {
+        self.varint().checked_sub(1)
+    }
+
+    fn u64(&mut self) -> u64 {
+        let bytes = &self.buf[self.pos..self.pos + 8];
+        self.pos += 8;
+        u64::from_le_bytes(bytes.try_into().expect("8 bytes"))
+    }
+
+    fn str(&mut self) -> &'static str {
+        let len = self.varint();
+        let s = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        std::str::from_utf8(s).expect("blob contains valid UTF-8")
+    }
+
+    fn list<T>(&mut self, mut item: impl FnMut(&mut Self) -> T) -> Vec<T> {
+        let len = self.varint();
+        (0..len).map(|_| item(self)).collect()
+    }
+
+    fn optional_str(&mut self) -> Option<&'static str> {
+        match self.varint() {
+            0 => None,
+            _ => Some(self.str()),
+        }
+    }
+
+    fn map<K, V>(&mut self, entry: impl FnMut(&mut Self) -> (K, V)) -> &'static Map<K, V> {
+        let key = self.u64();
+        let disps = leak(self.list(|r| (r.varint() as u32, r.varint() as u32)));
+        let entries = leak(self.list(entry));
+        Box::leak(Box::new(Map {
+            key,
+            disps,
+            entries,
+        }))
+    }
+}
+
+fn optionality(opt: usize) -> FieldOptionality {
+    match opt {
+        1 => FieldOptionality::Optional,
+        2 => FieldOptionality::Required,
+        3 => FieldOptionality::Conditional,
+        _ => FieldOptionality::BackwardCompatibility,
+    }
+}
+
+fn repeatability(rep: usize) -> FieldRepeatability {
+    match rep {
+        0 => FieldRepeatability::Unbounded,
+        1 => FieldRepeatability::Single,
+        n => FieldRepeatability::Bounded(n),
+    }
+}
+
+fn truncation(truncate: usize) -> Option<Truncation> {
+    match truncate {
+        0 => None,
+        1 => Some(Truncation::NotAllowed),
+        _ => Some(Truncation::Allowed),
+    }
+}
+
+fn status(status: usize) -> ValueStatus {
+    match status {
+        0 => ValueStatus::Active,
+        1 => ValueStatus::BackwardCompatibility,
+        2 => ValueStatus::Deprecated,
+        _ => ValueStatus::Withdrawn,
+    }
+}
+
+fn decode_tables() -> &'static Map<u16, &'static TableValues> {
+    let mut r = Reader::inflate(codegen::TABLES_BLOB);
+    r.map(|r| {
+        let id = r.varint() as u16;
+        let values = r.map(|r| {
+            let code = r.str();
+            let value = TableValue {
+                description: r.str(),
+                status: status(r.varint()),
+                added: r.optional_str(),
+                removed: r.optional_str(),
+                notes: r.optional_str(),
+            };
+            (code, value)
+        });
+        (id, values)
+    })
+}
+
+fn decode() -> Vec<(&'static str, &'static Definition)> {
+    let mut r = Reader::inflate(codegen::DEFINITIONS_BLOB);
+
+    let strings = r.list(|r| r.str());
+    let keys = r.list(|r| r.str());
+    // sub-field lists and fields are interleaved so that everything is decoded before anything
+    // links to it
+    let mut linked_tables = HashMap::new();
+    let mut table_link = |table: usize| -> Option<&'static Table> {
+        *linked_tables.entry(table).or_insert_with(|| {
+            u16::try_from(table)
+                .ok()
+                .and_then(get_table)
+                .map(|table| &*Box::leak(Box::new(table)))
+        })
+    };
+    let mut subfields: Vec<&'static [SubField]> = Vec::new();
+    let mut fields: Vec<&'static Field> = Vec::new();
+    for _ in 0..r.varint() {
+        if r.varint() == 0 {
+            let list = r.list(|r| {
+                let datatype = strings[r.varint()];
+                let description = strings[r.varint()];
+                let optionality = optionality(r.varint());
+                let repeatability = repeatability(r.varint());
+                let max_length = r.optional();
+                let conformance_length = r.optional();
+                let truncation = truncation(r.varint());
+                let table = r.optional();
+                let item_number = r.optional();
+                SubField {
+                    datatype,
+                    description,
+                    optionality,
+                    repeatability,
+                    max_length,
+                    conformance_length,
+                    truncation,
+                    table,
+                    item_number,
+                    datatype_link: Link(r.optional().map(|field| fields[field])),
+                    table_link: Link(table.and_then(&mut table_link)),
+                }
+            });
+            subfields.push(leak(list));
+        } else {
+            fields.push(Box::leak(Box::new(Field {
+                description: strings[r.varint()],
+                subfields: subfields[r.varint()],
+            })));
+        }
+    }
+    let segments = r.list(|r| Segment {
+        description: strings[r.varint()],
+        fields: subfields[r.varint()],
+    });
+    let segments = leak(segments);
+    let message_compounds = r.list(|r| {
+        leak(r.list(|r| MessageCompound {
+            name: r.optional().map(|name| strings[name]),
+            description: strings[r.varint()],
+            min: r.varint(),
+            max: r.varint(),
+        }))
+    });
+    // children are always encoded before their parents
+    let mut message_segments: Vec<&'static [MessageSegment]> = Vec::new();
+    for _ in 0..r.varint() {
+        let list = r.list(|r| MessageSegment {
+            name: strings[r.varint()],
+            description: strings[r.varint()],
+            min: r.varint(),
+            max: r.varint(),
+            children: r.optional().map(|children| message_segments[children]),
+            compounds: r.optional().map(|compounds| message_compounds[compounds]),
+            segment_link: Link(r.optional().map(|segment| &segments[segment])),
+        });
+        message_segments.push(leak(list));
+    }
+
+    r.list(|r| {
+        let version = keys[r.varint()];
+        let fields = r.map(|r| (keys[r.varint()], *fields[r.varint()]));
+        let segments = r.map(|r| (keys[r.varint()], segments[r.varint()]));
+        let messages = r.map(|r| {
+            let id = keys[r.varint()];
+            let message = Message {
+                name: strings[r.varint()],
+                description: strings[r.varint()],
+                segments: message_segments[r.varint()],
+            };
+            (id, message)
+        });
+        let definition: &'static Definition = Box::leak(Box::new(Definition {
+            fields,
+            segments,
+            messages,
+        }));
+        (version, definition)
+    })
+}
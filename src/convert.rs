@@ -0,0 +1,657 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+use crate::{
+    try_get_definition, Definition, FieldRepeatability, LengthCheck, LookupError, Segment,
+    SubField, Truncation,
+};
+
+/// The error returned when a message can't be converted or de-identified
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ConvertError<'a> {
+    /// The source or target version isn't available
+    Version(LookupError<'a>),
+    /// The message doesn't start with an `MSH` segment, so its separators are unknown
+    MissingHeader,
+}
+
+impl Display for ConvertError<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ConvertError::Version(error) => write!(f, "{error}"),
+            ConvertError::MissingHeader => write!(f, "message doesn't start with an MSH segment"),
+        }
+    }
+}
+
+impl core::error::Error for ConvertError<'_> {}
+
+impl<'a> From<LookupError<'a>> for ConvertError<'a> {
+    fn from(error: LookupError<'a>) -> Self {
+        ConvertError::Version(error)
+    }
+}
+
+/// What the converter did to a part of the message
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ChangeKind {
+    /// The segment was dropped because the target version doesn't define it
+    SegmentDropped,
+    /// The field was dropped because the target segment doesn't have it
+    FieldDropped,
+    /// Repetitions were dropped because the target field allows fewer
+    RepetitionsDropped {
+        /// How many repetitions were kept
+        kept: usize,
+    },
+    /// The component (or sub-component) was dropped because the target datatype doesn't have
+    /// it
+    ComponentDropped,
+    /// The value was truncated to the target length
+    Truncated {
+        /// The length of the original value
+        length: usize,
+        /// The length it was truncated to
+        limit: usize,
+    },
+    /// The value is longer than the target length but may not be truncated, so was kept as is
+    TooLong {
+        /// The length of the value
+        length: usize,
+        /// The target length
+        limit: usize,
+    },
+    /// The field's datatype is different in the target version, such as `CE` becoming `CWE`;
+    /// its components are mapped by position
+    Retyped {
+        /// The datatype in the source version
+        from: &'static str,
+        /// The datatype in the target version
+        to: &'static str,
+    },
+}
+
+impl ChangeKind {
+    /// Whether the change lost data
+    pub fn is_lossy(&self) -> bool {
+        !matches!(
+            self,
+            ChangeKind::TooLong { .. } | ChangeKind::Retyped { .. }
+        )
+    }
+}
+
+impl Display for ChangeKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ChangeKind::SegmentDropped => write!(f, "segment dropped"),
+            ChangeKind::FieldDropped => write!(f, "field dropped"),
+            ChangeKind::RepetitionsDropped { kept } => {
+                write!(f, "repetitions dropped, keeping {kept}")
+            }
+            ChangeKind::ComponentDropped => write!(f, "component dropped"),
+            ChangeKind::Truncated { length, limit } => {
+                write!(f, "truncated from {length} to {limit} characters")
+            }
+            ChangeKind::TooLong { length, limit } => write!(
+                f,
+                "{length} characters is longer than {limit} but may not be truncated"
+            ),
+            ChangeKind::Retyped { from, to } => write!(f, "datatype changed from {from} to {to}"),
+        }
+    }
+}
+
+/// A change made while converting a message, and where in the message it was made
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ConversionChange<'a> {
+    /// The 1-based position of the segment within the message
+    pub segment_number: usize,
+    /// The segment ID
+    pub segment: &'a str,
+    /// The 1-based field number, if the change is to a field or part of one
+    pub field: Option<usize>,
+    /// The 1-based repetition number, if the change is to a repetition or part of one
+    pub repetition: Option<usize>,
+    /// The 1-based component number, if the change is to a component or part of one
+    pub component: Option<usize>,
+    /// The 1-based sub-component number, if the change is to a sub-component
+    pub subcomponent: Option<usize>,
+    /// What was changed
+    pub kind: ChangeKind,
+}
+
+impl Display for ConversionChange<'_> {
+    /// Formats the change as its location and what happened, such as
+    /// `PID-5[1].1: truncated from 60 to 50 characters`
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.segment)?;
+        if let Some(field) = self.field {
+            write!(f, "-{field}")?;
+        }
+        if let Some(repetition) = self.repetition {
+            write!(f, "[{repetition}]")?;
+        }
+        for n in [self.component, self.subcomponent].into_iter().flatten() {
+            write!(f, ".{n}")?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+/// A message converted to another version by [convert_message]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Conversion<'a> {
+    /// The converted message, with segments separated by carriage returns
+    pub message: String,
+    /// Every change made to the message other than updating MSH-12, in message order
+    pub changes: Vec<ConversionChange<'a>>,
+}
+
+impl Conversion<'_> {
+    /// Whether the conversion kept all of the message's data
+    pub fn is_lossless(&self) -> bool {
+        !self.changes.iter().any(|change| change.kind.is_lossy())
+    }
+}
+
+/// The message's delimiters, from MSH-1 and MSH-2
+#[derive(Copy, Clone)]
+pub(crate) struct Separators {
+    pub(crate) field: char,
+    pub(crate) component: char,
+    pub(crate) repetition: char,
+    pub(crate) escape: char,
+    pub(crate) subcomponent: char,
+}
+
+impl Separators {
+    pub(crate) fn parse(message: &str) -> Option<Separators> {
+        let mut chars = message.strip_prefix("MSH")?.chars();
+        let field = chars.next()?;
+        let mut encoding = chars.take_while(|c| *c != field);
+        Some(Separators {
+            field,
+            component: encoding.next().unwrap_or('^'),
+            repetition: encoding.next().unwrap_or('~'),
+            escape: encoding.next().unwrap_or('\\'),
+            subcomponent: encoding.next().unwrap_or('&'),
+        })
+    }
+}
+
+/// A segment of an ER7-encoded message, as split by [segments]
+#[derive(Copy, Clone)]
+pub(crate) struct Er7Segment<'a> {
+    /// The segment's position in the message, counting from 1
+    pub(crate) number: usize,
+    /// The whole segment
+    pub(crate) text: &'a str,
+    /// The segment ID
+    pub(crate) id: &'a str,
+    separator: char,
+}
+
+impl<'a> Er7Segment<'a> {
+    /// The number of the segment's first field: MSH-1 is the field separator itself, so the
+    /// text after `MSH|` starts at MSH-2
+    pub(crate) fn first_field(&self) -> usize {
+        if self.id == "MSH" {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// The segment's fields and their numbers, in order
+    pub(crate) fn fields(&self) -> impl Iterator<Item = (usize, &'a str)> {
+        let (first, separator) = (self.first_field(), self.separator);
+        self.text[self.id.len()..]
+            .strip_prefix(separator)
+            .into_iter()
+            .flat_map(move |fields| fields.split(separator))
+            .enumerate()
+            .map(move |(i, text)| (i + first, text))
+    }
+}
+
+/// Split an ER7-encoded message into its segments, which may be separated by carriage returns,
+/// line feeds or both
+pub(crate) fn segments(
+    message: &str,
+    separators: Separators,
+) -> impl Iterator<Item = Er7Segment<'_>> {
+    message
+        .split(['\r', '\n'])
+        .filter(|segment| !segment.is_empty())
+        .enumerate()
+        .map(move |(i, text)| Er7Segment {
+            number: i + 1,
+            text,
+            id: text.split(separators.field).next().unwrap_or(text),
+            separator: separators.field,
+        })
+}
+
+/// Convert an ER7-encoded message from one version to another, using the definitions of both
+///
+/// Segments the target version doesn't define are dropped, as are fields, repetitions and
+/// components the target doesn't allow. Primitive values longer than the target length are
+/// truncated, without splitting escape sequences, unless the target says they may not be. When
+/// a field's datatype differs between the versions (`TS` becoming `DTM`, say), its components
+/// are mapped by position. MSH-12 is set to the target version. Segments neither version
+/// defines, such as `Z` segments, are passed through unchanged. Lengths are counted in encoded
+/// characters, so escape sequences count in full.
+///
+/// Segments may be separated by carriage returns, line feeds or both; the converted message
+/// always uses carriage returns.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let message = "MSH|^~\\&|HUB||LEGACY||20240101120000||ADT^A01|1|P|2.5.1\rPID|1||123";
+/// let conversion = convert_message(message, "2.5.1", "2.3").expect("can convert");
+/// assert!(conversion.message.ends_with("|2.3\rPID|1||123"));
+/// for change in conversion.changes.iter().filter(|change| change.kind.is_lossy()) {
+///     println!("{change}");
+/// }
+/// ```
+pub fn convert_message<'a, 'v>(
+    message: &'a str,
+    from: &'v str,
+    to: &'v str,
+) -> Result<Conversion<'a>, ConvertError<'v>> {
+    let source = try_get_definition(from)?;
+    let target = try_get_definition(to)?;
+    let separators = Separators::parse(message).ok_or(ConvertError::MissingHeader)?;
+    let mut converter = Converter {
+        separators,
+        target_version: to,
+        changes: Vec::new(),
+        out: String::with_capacity(message.len()),
+    };
+
+    for segment in segments(message, separators) {
+        converter.segment(segment, source, target);
+    }
+    Ok(Conversion {
+        message: converter.out,
+        changes: converter.changes,
+    })
+}
+
+/// Where in the message the converter currently is
+#[derive(Copy, Clone)]
+struct Location<'a> {
+    segment_number: usize,
+    segment: &'a str,
+    field: Option<usize>,
+    repetition: Option<usize>,
+    component: Option<usize>,
+    subcomponent: Option<usize>,
+}
+
+impl Location<'_> {
+    /// The location of the `n`th component of this location
+    fn child(self, n: usize) -> Self {
+        match self.component {
+            None => Location {
+                component: Some(n),
+                ..self
+            },
+            Some(_) => Location {
+                subcomponent: Some(n),
+                ..self
+            },
+        }
+    }
+}
+
+struct Converter<'a, 'v> {
+    separators: Separators,
+    target_version: &'v str,
+    changes: Vec<ConversionChange<'a>>,
+    out: String,
+}
+
+impl<'a> Converter<'a, '_> {
+    fn change(&mut self, location: Location<'a>, kind: ChangeKind) {
+        self.changes.push(ConversionChange {
+            segment_number: location.segment_number,
+            segment: location.segment,
+            field: location.field,
+            repetition: location.repetition,
+            component: location.component,
+            subcomponent: location.subcomponent,
+            kind,
+        });
+    }
+
+    fn segment(
+        &mut self,
+        segment: Er7Segment<'a>,
+        source: &'static Definition,
+        target: &'static Definition,
+    ) {
+        let separator = self.separators.field;
+        let id = segment.id;
+        let location = Location {
+            segment_number: segment.number,
+            segment: id,
+            field: None,
+            repetition: None,
+            component: None,
+            subcomponent: None,
+        };
+        let Some(target_segment) = target.segments.get(id) else {
+            if source.segments.contains_key(id) {
+                self.change(location, ChangeKind::SegmentDropped);
+            } else {
+                self.push_segment(segment.text);
+            }
+            return;
+        };
+
+        if !self.out.is_empty() {
+            self.out.push('\r');
+        }
+        self.out.push_str(id);
+        let is_header = id == "MSH";
+        let mut fields = segment.fields().collect::<Vec<_>>();
+        // MSH-12 is always written, so that it can be set to the target version
+        while is_header && fields.len() < 11 {
+            fields.push((fields.len() + 2, ""));
+        }
+
+        for (field, text) in fields {
+            let location = Location {
+                field: Some(field),
+                ..location
+            };
+            let Some(target_field) = target_segment.fields.get(field - 1) else {
+                if !text.is_empty() {
+                    self.change(location, ChangeKind::FieldDropped);
+                }
+                continue;
+            };
+            self.out.push(separator);
+            match field {
+                2 if is_header => self.out.push_str(text),
+                12 if is_header => self.version_field(text, target_field, location),
+                _ => {
+                    self.retyped(source.segments.get(id), field, target_field, location);
+                    self.field(text, target_field, location);
+                }
+            }
+        }
+        self.trim_trailing(separator);
+    }
+
+    fn push_segment(&mut self, text: &str) {
+        if !self.out.is_empty() {
+            self.out.push('\r');
+        }
+        self.out.push_str(text);
+    }
+
+    /// Remove trailing separators left by empty or dropped items
+    fn trim_trailing(&mut self, separator: char) {
+        while self.out.ends_with(separator) {
+            self.out.pop();
+        }
+    }
+
+    fn retyped(
+        &mut self,
+        source: Option<&'static Segment>,
+        field: usize,
+        target: &'static SubField,
+        location: Location<'a>,
+    ) {
+        let Some(source) = source.and_then(|segment| segment.fields.get(field - 1)) else {
+            return;
+        };
+        if source.datatype != target.datatype {
+            let kind = ChangeKind::Retyped {
+                from: source.datatype,
+                to: target.datatype,
+            };
+            self.change(location, kind);
+        }
+    }
+
+    /// Write MSH-12, replacing the version ID while keeping any other components
+    fn version_field(&mut self, text: &str, target: &'static SubField, location: Location<'a>) {
+        let rest = text
+            .find(self.separators.component)
+            .map_or("", |i| &text[i..]);
+        let mut version = String::from(self.target_version);
+        version.push_str(rest);
+        self.value(&version, target, location);
+    }
+
+    fn field(&mut self, text: &'a str, target: &'static SubField, location: Location<'a>) {
+        let repetition = self.separators.repetition;
+        let kept = match target.repeatability {
+            FieldRepeatability::Unbounded => usize::MAX,
+            FieldRepeatability::Single => 1,
+            FieldRepeatability::Bounded(n) => n,
+        };
+        for (r, text) in text.split(repetition).enumerate() {
+            if r >= kept {
+                if !text.is_empty() {
+                    let kind = ChangeKind::RepetitionsDropped { kept };
+                    self.change(location, kind);
+                    break;
+                }
+                continue;
+            }
+            if r > 0 {
+                self.out.push(repetition);
+            }
+            let location = Location {
+                repetition: Some(r + 1),
+                ..location
+            };
+            self.value(text, target, location);
+        }
+        self.trim_trailing(repetition);
+    }
+
+    /// Write a repetition, component or sub-component, mapping its parts onto the target
+    /// datatype's components by position
+    fn value(&mut self, text: &str, target: &'static SubField, location: Location<'a>) {
+        let separator = match (location.component, location.subcomponent) {
+            (None, _) => Some(self.separators.component),
+            (Some(_), None) => Some(self.separators.subcomponent),
+            (Some(_), Some(_)) => None,
+        };
+        let components = target
+            .datatype_definition()
+            .map(|field| field.subfields)
+            .unwrap_or_default();
+
+        let Some(separator) = separator else {
+            return self.primitive(text, target, location);
+        };
+        let mut parts = text.split(separator).enumerate();
+        if components.is_empty() {
+            let (_, first) = parts.next().unwrap_or_default();
+            self.primitive(first, target, location);
+        } else {
+            for (i, part) in parts.by_ref().take(components.len()) {
+                if i > 0 {
+                    self.out.push(separator);
+                }
+                self.value(part, &components[i], location.child(i + 1));
+            }
+            self.trim_trailing(separator);
+        }
+        for (i, part) in parts {
+            if !part.is_empty() {
+                self.change(location.child(i + 1), ChangeKind::ComponentDropped);
+            }
+        }
+    }
+
+    fn primitive(&mut self, text: &str, target: &'static SubField, location: Location<'a>) {
+        let length = text.chars().count();
+        let limit = match target.check_length(length) {
+            LengthCheck::Valid => None,
+            LengthCheck::TooLong(limit) if target.truncation == Some(Truncation::NotAllowed) => {
+                self.change(location, ChangeKind::TooLong { length, limit });
+                None
+            }
+            LengthCheck::Truncatable(limit) | LengthCheck::TooLong(limit) => Some(limit),
+        };
+        match limit {
+            Some(limit) => {
+                self.out
+                    .push_str(truncate(text, limit, self.separators.escape));
+                self.change(location, ChangeKind::Truncated { length, limit });
+            }
+            None => self.out.push_str(text),
+        }
+    }
+}
+
+/// The first `limit` characters of `text`, backing off so that an escape sequence isn't split
+fn truncate(text: &str, limit: usize, escape: char) -> &str {
+    let end = text
+        .char_indices()
+        .nth(limit)
+        .map_or(text.len(), |(i, _)| i);
+    let truncated = &text[..end];
+    if truncated.matches(escape).count() % 2 == 1 {
+        let start = truncated.rfind(escape).unwrap_or(0);
+        return &truncated[..start];
+    }
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(all(feature = "23", feature = "251"))]
+    use crate::get_segment;
+    #[cfg(all(feature = "23", feature = "251"))]
+    use alloc::format;
+    #[cfg(all(feature = "23", feature = "251"))]
+    use alloc::string::ToString;
+
+    #[cfg(all(feature = "23", feature = "251"))]
+    fn convert(message: &str) -> Conversion<'_> {
+        convert_message(message, "2.5.1", "2.3").expect("can convert")
+    }
+
+    #[cfg(all(feature = "23", feature = "251"))]
+    #[test]
+    fn rewrites_the_version() {
+        let conversion = convert("MSH|^~\\&|A||B||20240101||ACK|1|P|2.5.1\nMSA|AA|1\n");
+        assert_eq!(
+            conversion.message,
+            "MSH|^~\\&|A||B||20240101||ACK|1|P|2.3\rMSA|AA|1"
+        );
+        let conversion = convert("MSH|^~\\&|A");
+        assert!(conversion.message.ends_with("|2.3"));
+        assert_eq!(
+            convert_message("PID|1", "2.5.1", "2.3"),
+            Err(ConvertError::MissingHeader)
+        );
+        assert!(matches!(
+            convert_message("MSH|^~\\&", "2.5.1", "2.5.1-US"),
+            Err(ConvertError::Version(LookupError::UnknownVersion { .. }))
+        ));
+    }
+
+    #[cfg(all(feature = "23", feature = "251"))]
+    #[test]
+    fn drops_what_the_target_does_not_define() {
+        let pid = get_segment("2.3", "PID").expect("can get PID");
+        let xpn = pid.fields[4]
+            .datatype_definition()
+            .expect("PID-5 is composite");
+        let mut name = "^".repeat(xpn.subfields.len());
+        name.push('X');
+        let extra_fields = "|".repeat(pid.fields.len() - 5);
+        let message = format!("MSH|^~\\&|A\rPID|1~2||||{name}{extra_fields}|X\rZPI|kept|as is");
+        let conversion = convert(&message);
+        let changes = conversion
+            .changes
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [
+                "PID-1: repetitions dropped, keeping 1".to_string(),
+                format!("PID-5[1].{}: component dropped", xpn.subfields.len() + 1),
+                format!("PID-{}: field dropped", pid.fields.len() + 1),
+            ]
+        );
+        assert!(!conversion.is_lossless());
+        assert!(conversion.message.contains("\rPID|1\r"));
+        assert!(conversion.message.ends_with("\rZPI|kept|as is"));
+
+        let dropped = crate::get_definition("2.5.1")
+            .expect("can get 2.5.1")
+            .segments
+            .keys()
+            .filter(|segment| get_segment("2.3", segment).is_none());
+        for segment in dropped {
+            let message = format!("MSH|^~\\&|A\r{segment}|1");
+            let conversion = convert(&message);
+            assert_eq!(conversion.changes[0].kind, ChangeKind::SegmentDropped);
+            assert!(!conversion.message.contains('\r'));
+        }
+    }
+
+    #[cfg(all(feature = "23", feature = "251"))]
+    #[test]
+    fn truncates_to_target_lengths() {
+        let pid = get_segment("2.3", "PID").expect("can get PID");
+        let (field, limit) = pid
+            .fields
+            .iter()
+            .enumerate()
+            .find_map(|(i, field)| {
+                let primitive = field
+                    .datatype_definition()
+                    .is_none_or(|d| d.subfields.is_empty());
+                field
+                    .max_length
+                    .filter(|_| primitive)
+                    .map(|limit| (i + 1, limit))
+            })
+            .expect("PID has a primitive field with a length");
+        let message = format!(
+            "MSH|^~\\&|A\rPID{}|{}",
+            "|".repeat(field - 1),
+            "A".repeat(limit + 5)
+        );
+        let conversion = convert(&message);
+        let truncated = conversion
+            .changes
+            .iter()
+            .find(|change| change.field == Some(field))
+            .expect("the field was truncated");
+        assert_eq!(
+            truncated.kind,
+            ChangeKind::Truncated {
+                length: limit + 5,
+                limit
+            }
+        );
+        assert!(conversion.message.ends_with(&"A".repeat(limit)));
+    }
+
+    #[test]
+    fn keeps_escape_sequences_whole() {
+        assert_eq!(truncate("AB\\F\\CD", 3, '\\'), "AB");
+        assert_eq!(truncate("AB\\F\\CD", 5, '\\'), "AB\\F\\");
+        assert_eq!(truncate("ÄÖÜ", 2, '\\'), "ÄÖ");
+        assert_eq!(truncate("AB", 5, '\\'), "AB");
+    }
+}
@@ -0,0 +1,820 @@
+//! A C ABI over the definitions, available with the `ffi` feature
+//!
+//! Definitions, segments, fields and messages are handed out as opaque pointers into the
+//! library's static data, so they never need to be freed and remain valid for the lifetime of
+//! the program. Strings are returned as an [Hl7Str] (a pointer and a length, _not_
+//! NUL-terminated); a missing string has a null `ptr`. Strings passed _in_ must be
+//! NUL-terminated.
+//!
+//! The matching C header is `include/hl7_definitions.h`, generated with `cbindgen`.
+
+use core::ffi::{c_char, CStr};
+use core::ptr;
+
+use crate::{
+    Definition, Field, FieldOptionality, FieldRepeatability, Message, MessageCompound,
+    MessageSegment, Segment, SubField, VERSIONS,
+};
+
+/// A borrowed UTF-8 string which is _not_ NUL-terminated
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct Hl7Str {
+    /// Pointer to the first byte of the string, or null if there is no string
+    pub ptr: *const c_char,
+    /// The length of the string in bytes
+    pub len: usize,
+}
+
+impl Hl7Str {
+    const NULL: Hl7Str = Hl7Str {
+        ptr: ptr::null(),
+        len: 0,
+    };
+}
+
+impl From<&'static str> for Hl7Str {
+    fn from(s: &'static str) -> Self {
+        Hl7Str {
+            ptr: s.as_ptr().cast(),
+            len: s.len(),
+        }
+    }
+}
+
+impl From<Option<&'static str>> for Hl7Str {
+    fn from(s: Option<&'static str>) -> Self {
+        s.map(Hl7Str::from).unwrap_or(Hl7Str::NULL)
+    }
+}
+
+/// How "required" a field is; see [FieldOptionality]
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Hl7Optionality {
+    Optional,
+    Required,
+    Conditional,
+    BackwardCompatibility,
+}
+
+impl From<FieldOptionality> for Hl7Optionality {
+    fn from(optionality: FieldOptionality) -> Self {
+        match optionality {
+            FieldOptionality::Optional => Hl7Optionality::Optional,
+            FieldOptionality::Required => Hl7Optionality::Required,
+            FieldOptionality::Conditional => Hl7Optionality::Conditional,
+            FieldOptionality::BackwardCompatibility => Hl7Optionality::BackwardCompatibility,
+        }
+    }
+}
+
+/// How many times a field can be repeated; see [FieldRepeatability]
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Hl7Repeatability {
+    Unbounded,
+    Single,
+    Bounded,
+}
+
+unsafe fn as_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(s) }.to_str().ok()
+}
+
+unsafe fn with<T: 'static, R>(value: *const T, default: R, f: impl FnOnce(&'static T) -> R) -> R {
+    match unsafe { value.as_ref() } {
+        Some(value) => f(value),
+        None => default,
+    }
+}
+
+fn opt<T>(value: Option<&'static T>) -> *const T {
+    value.map_or(ptr::null(), |value| value)
+}
+
+unsafe fn write<T>(dest: *mut T, value: T) {
+    if !dest.is_null() {
+        unsafe { dest.write(value) };
+    }
+}
+
+/// The number of versions compiled into the library
+#[no_mangle]
+pub extern "C" fn hl7_version_count() -> usize {
+    VERSIONS.len()
+}
+
+/// The version at `index` (oldest first), or a null string if `index` is out of range
+#[no_mangle]
+pub extern "C" fn hl7_version_at(index: usize) -> Hl7Str {
+    VERSIONS.get(index).copied().into()
+}
+
+/// Get the definition for a version, or null if the version isn't compiled in
+///
+/// # Safety
+///
+/// `version` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_definition(version: *const c_char) -> *const Definition {
+    opt(unsafe { as_str(version) }.and_then(crate::get_definition))
+}
+
+/// Get a field from a definition by its ID, or null if it doesn't exist
+///
+/// # Safety
+///
+/// `definition` must be null or a pointer returned by [hl7_definition], and `id` must be null
+/// or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_definition_field(
+    definition: *const Definition,
+    id: *const c_char,
+) -> *const Field {
+    let Some(id) = (unsafe { as_str(id) }) else {
+        return ptr::null();
+    };
+    unsafe {
+        with(definition, ptr::null(), |definition| {
+            opt(definition.fields.get(id))
+        })
+    }
+}
+
+/// The number of fields in a definition
+///
+/// # Safety
+///
+/// `definition` must be null or a pointer returned by [hl7_definition].
+#[no_mangle]
+pub unsafe extern "C" fn hl7_definition_field_count(definition: *const Definition) -> usize {
+    unsafe { with(definition, 0, |definition| definition.fields.len()) }
+}
+
+/// Get the field at `index` in a definition (in no particular order), writing its ID to `id`
+/// if `id` isn't null. Returns null if `index` is out of range.
+///
+/// # Safety
+///
+/// `definition` must be null or a pointer returned by [hl7_definition], and `id` must be null
+/// or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_definition_field_at(
+    definition: *const Definition,
+    index: usize,
+    id: *mut Hl7Str,
+) -> *const Field {
+    let entry = unsafe {
+        with(definition, None, |definition| {
+            definition.fields.entries.get(index)
+        })
+    };
+    let Some((key, value)) = entry else {
+        return ptr::null();
+    };
+    unsafe { write(id, Hl7Str::from(*key)) };
+    value
+}
+
+/// Get a segment from a definition by its ID, or null if it doesn't exist
+///
+/// # Safety
+///
+/// `definition` must be null or a pointer returned by [hl7_definition], and `id` must be null
+/// or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_definition_segment(
+    definition: *const Definition,
+    id: *const c_char,
+) -> *const Segment {
+    let Some(id) = (unsafe { as_str(id) }) else {
+        return ptr::null();
+    };
+    unsafe {
+        with(definition, ptr::null(), |definition| {
+            opt(definition.segments.get(id))
+        })
+    }
+}
+
+/// The number of segments in a definition
+///
+/// # Safety
+///
+/// `definition` must be null or a pointer returned by [hl7_definition].
+#[no_mangle]
+pub unsafe extern "C" fn hl7_definition_segment_count(definition: *const Definition) -> usize {
+    unsafe { with(definition, 0, |definition| definition.segments.len()) }
+}
+
+/// Get the segment at `index` in a definition (in no particular order), writing its ID to `id`
+/// if `id` isn't null. Returns null if `index` is out of range.
+///
+/// # Safety
+///
+/// `definition` must be null or a pointer returned by [hl7_definition], and `id` must be null
+/// or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_definition_segment_at(
+    definition: *const Definition,
+    index: usize,
+    id: *mut Hl7Str,
+) -> *const Segment {
+    let entry = unsafe {
+        with(definition, None, |definition| {
+            definition.segments.entries.get(index)
+        })
+    };
+    let Some((key, value)) = entry else {
+        return ptr::null();
+    };
+    unsafe { write(id, Hl7Str::from(*key)) };
+    value
+}
+
+/// Get a message from a definition by its ID, or null if it doesn't exist
+///
+/// # Safety
+///
+/// `definition` must be null or a pointer returned by [hl7_definition], and `id` must be null
+/// or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_definition_message(
+    definition: *const Definition,
+    id: *const c_char,
+) -> *const Message {
+    let Some(id) = (unsafe { as_str(id) }) else {
+        return ptr::null();
+    };
+    unsafe {
+        with(definition, ptr::null(), |definition| {
+            opt(definition.messages.get(id))
+        })
+    }
+}
+
+/// The number of messages in a definition
+///
+/// # Safety
+///
+/// `definition` must be null or a pointer returned by [hl7_definition].
+#[no_mangle]
+pub unsafe extern "C" fn hl7_definition_message_count(definition: *const Definition) -> usize {
+    unsafe { with(definition, 0, |definition| definition.messages.len()) }
+}
+
+/// Get the message at `index` in a definition (in no particular order), writing its ID to `id`
+/// if `id` isn't null. Returns null if `index` is out of range.
+///
+/// # Safety
+///
+/// `definition` must be null or a pointer returned by [hl7_definition], and `id` must be null
+/// or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_definition_message_at(
+    definition: *const Definition,
+    index: usize,
+    id: *mut Hl7Str,
+) -> *const Message {
+    let entry = unsafe {
+        with(definition, None, |definition| {
+            definition.messages.entries.get(index)
+        })
+    };
+    let Some((key, value)) = entry else {
+        return ptr::null();
+    };
+    unsafe { write(id, Hl7Str::from(*key)) };
+    value
+}
+
+/// The description of a field
+///
+/// # Safety
+///
+/// `field` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_field_description(field: *const Field) -> Hl7Str {
+    unsafe { with(field, Hl7Str::NULL, |field| field.description.into()) }
+}
+
+/// The number of sub-fields (components) of a field
+///
+/// # Safety
+///
+/// `field` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_field_subfield_count(field: *const Field) -> usize {
+    unsafe { with(field, 0, |field| field.subfields.len()) }
+}
+
+/// Get one of the sub-fields (components) of a field, or null if `index` is out of range
+///
+/// # Safety
+///
+/// `field` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_field_subfield_at(
+    field: *const Field,
+    index: usize,
+) -> *const SubField {
+    unsafe { with(field, ptr::null(), |field| opt(field.subfields.get(index))) }
+}
+
+/// The description of a segment
+///
+/// # Safety
+///
+/// `segment` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_segment_description(segment: *const Segment) -> Hl7Str {
+    unsafe { with(segment, Hl7Str::NULL, |segment| segment.description.into()) }
+}
+
+/// The number of fields of a segment
+///
+/// # Safety
+///
+/// `segment` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_segment_field_count(segment: *const Segment) -> usize {
+    unsafe { with(segment, 0, |segment| segment.fields.len()) }
+}
+
+/// Get one of the fields of a segment, or null if `index` is out of range
+///
+/// # Safety
+///
+/// `segment` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_segment_field_at(
+    segment: *const Segment,
+    index: usize,
+) -> *const SubField {
+    unsafe {
+        with(segment, ptr::null(), |segment| {
+            opt(segment.fields.get(index))
+        })
+    }
+}
+
+/// The datatype of a sub-field
+///
+/// # Safety
+///
+/// `subfield` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_subfield_datatype(subfield: *const SubField) -> Hl7Str {
+    unsafe { with(subfield, Hl7Str::NULL, |subfield| subfield.datatype.into()) }
+}
+
+/// The description of a sub-field
+///
+/// # Safety
+///
+/// `subfield` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_subfield_description(subfield: *const SubField) -> Hl7Str {
+    unsafe {
+        with(subfield, Hl7Str::NULL, |subfield| {
+            subfield.description.into()
+        })
+    }
+}
+
+/// Whether a sub-field is required
+///
+/// # Safety
+///
+/// `subfield` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_subfield_optionality(subfield: *const SubField) -> Hl7Optionality {
+    unsafe {
+        with(subfield, Hl7Optionality::Optional, |subfield| {
+            subfield.optionality.into()
+        })
+    }
+}
+
+/// How many times a sub-field can be repeated. If the repeatability is
+/// [Hl7Repeatability::Bounded], the maximum number of repetitions is written to `max`.
+///
+/// # Safety
+///
+/// `subfield` must be null or a pointer handed out by this library, and `max` must be null or
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_subfield_repeatability(
+    subfield: *const SubField,
+    max: *mut usize,
+) -> Hl7Repeatability {
+    match unsafe { subfield.as_ref() }.map(|subfield| subfield.repeatability) {
+        Some(FieldRepeatability::Bounded(n)) => {
+            unsafe { write(max, n) };
+            Hl7Repeatability::Bounded
+        }
+        Some(FieldRepeatability::Unbounded) => Hl7Repeatability::Unbounded,
+        Some(FieldRepeatability::Single) | None => Hl7Repeatability::Single,
+    }
+}
+
+/// Write the maximum length of a sub-field to `max_length`, returning `false` if it is
+/// unbounded or not applicable
+///
+/// # Safety
+///
+/// `subfield` must be null or a pointer handed out by this library, and `max_length` must be
+/// null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_subfield_max_length(
+    subfield: *const SubField,
+    max_length: *mut usize,
+) -> bool {
+    match unsafe { subfield.as_ref() }.and_then(|subfield| subfield.max_length) {
+        Some(n) => {
+            unsafe { write(max_length, n) };
+            true
+        }
+        None => false,
+    }
+}
+
+/// Write the conformance length of a sub-field to `conformance_length`, returning `false` if
+/// it isn't specified
+///
+/// # Safety
+///
+/// `subfield` must be null or a pointer handed out by this library, and `conformance_length`
+/// must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_subfield_conformance_length(
+    subfield: *const SubField,
+    conformance_length: *mut usize,
+) -> bool {
+    match unsafe { subfield.as_ref() }.and_then(|subfield| subfield.conformance_length) {
+        Some(n) => {
+            unsafe { write(conformance_length, n) };
+            true
+        }
+        None => false,
+    }
+}
+
+/// Whether values of a sub-field longer than its conformance length may be truncated to it
+///
+/// # Safety
+///
+/// `subfield` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_subfield_may_truncate(subfield: *const SubField) -> bool {
+    unsafe { with(subfield, false, |subfield| subfield.may_truncate()) }
+}
+
+/// Write the item number of a sub-field's data element to `item_number`, returning `false` if
+/// it isn't known
+///
+/// # Safety
+///
+/// `subfield` must be null or a pointer handed out by this library, and `item_number` must be
+/// null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_subfield_item_number(
+    subfield: *const SubField,
+    item_number: *mut usize,
+) -> bool {
+    match unsafe { subfield.as_ref() }.and_then(|subfield| subfield.item_number) {
+        Some(n) => {
+            unsafe { write(item_number, n) };
+            true
+        }
+        None => false,
+    }
+}
+
+/// Write the number of the table holding the sub-field's valid values to `table`, returning
+/// `false` if the sub-field isn't coded
+///
+/// # Safety
+///
+/// `subfield` must be null or a pointer handed out by this library, and `table` must be null
+/// or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_subfield_table(subfield: *const SubField, table: *mut u16) -> bool {
+    match unsafe { subfield.as_ref() }.and_then(|subfield| subfield.table) {
+        Some(n) => {
+            unsafe { write(table, n as u16) };
+            true
+        }
+        None => false,
+    }
+}
+
+/// The name of a message
+///
+/// # Safety
+///
+/// `message` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_message_name(message: *const Message) -> Hl7Str {
+    unsafe { with(message, Hl7Str::NULL, |message| message.name.into()) }
+}
+
+/// The description of a message
+///
+/// # Safety
+///
+/// `message` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_message_description(message: *const Message) -> Hl7Str {
+    unsafe { with(message, Hl7Str::NULL, |message| message.description.into()) }
+}
+
+/// The number of top-level segments of a message
+///
+/// # Safety
+///
+/// `message` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_message_segment_count(message: *const Message) -> usize {
+    unsafe { with(message, 0, |message| message.segments.len()) }
+}
+
+/// Get one of the top-level segments of a message, or null if `index` is out of range
+///
+/// # Safety
+///
+/// `message` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_message_segment_at(
+    message: *const Message,
+    index: usize,
+) -> *const MessageSegment {
+    unsafe {
+        with(message, ptr::null(), |message| {
+            opt(message.segments.get(index))
+        })
+    }
+}
+
+/// The name (segment ID) of a segment within a message
+///
+/// # Safety
+///
+/// `segment` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_message_segment_name(segment: *const MessageSegment) -> Hl7Str {
+    unsafe { with(segment, Hl7Str::NULL, |segment| segment.name.into()) }
+}
+
+/// The description of a segment within a message
+///
+/// # Safety
+///
+/// `segment` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_message_segment_description(segment: *const MessageSegment) -> Hl7Str {
+    unsafe { with(segment, Hl7Str::NULL, |segment| segment.description.into()) }
+}
+
+/// The minimum number of times a segment must appear in a message
+///
+/// # Safety
+///
+/// `segment` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_message_segment_min(segment: *const MessageSegment) -> usize {
+    unsafe { with(segment, 0, |segment| segment.min) }
+}
+
+/// The maximum number of times a segment may appear in a message
+///
+/// # Safety
+///
+/// `segment` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_message_segment_max(segment: *const MessageSegment) -> usize {
+    unsafe { with(segment, 0, |segment| segment.max) }
+}
+
+/// The number of child segments of a segment within a message
+///
+/// # Safety
+///
+/// `segment` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_message_segment_child_count(segment: *const MessageSegment) -> usize {
+    unsafe {
+        with(segment, 0, |segment| {
+            segment.children.unwrap_or_default().len()
+        })
+    }
+}
+
+/// Get one of the child segments of a segment within a message, or null if `index` is out of range
+///
+/// # Safety
+///
+/// `segment` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_message_segment_child_at(
+    segment: *const MessageSegment,
+    index: usize,
+) -> *const MessageSegment {
+    unsafe {
+        with(segment, ptr::null(), |segment| {
+            opt(segment.children.unwrap_or_default().get(index))
+        })
+    }
+}
+
+/// The number of choices (compounds) of a segment within a message
+///
+/// # Safety
+///
+/// `segment` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_message_segment_compound_count(
+    segment: *const MessageSegment,
+) -> usize {
+    unsafe {
+        with(segment, 0, |segment| {
+            segment.compounds.unwrap_or_default().len()
+        })
+    }
+}
+
+/// Get one of the choices (compounds) of a segment within a message, or null if `index` is out of range
+///
+/// # Safety
+///
+/// `segment` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_message_segment_compound_at(
+    segment: *const MessageSegment,
+    index: usize,
+) -> *const MessageCompound {
+    unsafe {
+        with(segment, ptr::null(), |segment| {
+            opt(segment.compounds.unwrap_or_default().get(index))
+        })
+    }
+}
+
+/// The name of a compound, or a null string if it has none
+///
+/// # Safety
+///
+/// `compound` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_message_compound_name(compound: *const MessageCompound) -> Hl7Str {
+    unsafe { with(compound, Hl7Str::NULL, |compound| compound.name.into()) }
+}
+
+/// The description of a compound
+///
+/// # Safety
+///
+/// `compound` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_message_compound_description(
+    compound: *const MessageCompound,
+) -> Hl7Str {
+    unsafe {
+        with(compound, Hl7Str::NULL, |compound| {
+            compound.description.into()
+        })
+    }
+}
+
+/// The minimum number of times a compound must appear
+///
+/// # Safety
+///
+/// `compound` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_message_compound_min(compound: *const MessageCompound) -> usize {
+    unsafe { with(compound, 0, |compound| compound.min) }
+}
+
+/// The maximum number of times a compound may appear
+///
+/// # Safety
+///
+/// `compound` must be null or a pointer handed out by this library.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_message_compound_max(compound: *const MessageCompound) -> usize {
+    unsafe { with(compound, 0, |compound| compound.max) }
+}
+
+/// Resolve a path such as `PID-5.1` in the given version, returning the most specific
+/// sub-field it refers to, or null if it can't be resolved
+///
+/// # Safety
+///
+/// `version` and `path` must be null or valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_resolve_path(
+    version: *const c_char,
+    path: *const c_char,
+) -> *const SubField {
+    let (Some(version), Some(path)) = (unsafe { as_str(version) }, unsafe { as_str(path) }) else {
+        return ptr::null();
+    };
+    opt(crate::resolve_path(version, path).map(|resolved| resolved.target()))
+}
+
+/// The description of a table, or a null string if the table doesn't exist
+#[no_mangle]
+pub extern "C" fn hl7_table_description(table: u16) -> Hl7Str {
+    crate::table_description(table).into()
+}
+
+/// The description of a value in a table, or a null string if it doesn't exist
+///
+/// # Safety
+///
+/// `code` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_table_value(table: u16, code: *const c_char) -> Hl7Str {
+    unsafe { as_str(code) }
+        .and_then(|code| crate::table_value(table, code))
+        .into()
+}
+
+/// The number of values in a table
+#[no_mangle]
+pub extern "C" fn hl7_table_value_count(table: u16) -> usize {
+    crate::table_values(table).map_or(0, <[_]>::len)
+}
+
+/// Get the description of the value at `index` in a table (in no particular order), writing
+/// its code to `code` if `code` isn't null. Returns a null string if `index` is out of range.
+///
+/// # Safety
+///
+/// `code` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn hl7_table_value_at(table: u16, index: usize, code: *mut Hl7Str) -> Hl7Str {
+    let Some((key, value)) = crate::table_values(table).and_then(|values| values.get(index)) else {
+        return Hl7Str::NULL;
+    };
+    unsafe { write(code, Hl7Str::from(*key)) };
+    value.description.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_str(s: Hl7Str) -> &'static str {
+        assert!(!s.ptr.is_null());
+        let bytes = unsafe { core::slice::from_raw_parts(s.ptr.cast::<u8>(), s.len) };
+        core::str::from_utf8(bytes).expect("valid UTF-8")
+    }
+
+    #[test]
+    fn can_walk_definitions() {
+        unsafe {
+            let definition = hl7_definition(c"2.5.1".as_ptr());
+            assert!(!definition.is_null());
+            assert!(hl7_definition(c"9.9".as_ptr()).is_null());
+            assert!(hl7_definition(ptr::null()).is_null());
+
+            let msh = hl7_definition_segment(definition, c"MSH".as_ptr());
+            assert_eq!(to_str(hl7_segment_description(msh)), "Message Header");
+            assert_eq!(hl7_segment_field_count(msh), 21);
+            let control_id = hl7_segment_field_at(msh, 9);
+            assert_eq!(
+                to_str(hl7_subfield_description(control_id)),
+                "Message Control ID"
+            );
+            assert!(hl7_segment_field_at(msh, 21).is_null());
+
+            let count = hl7_definition_message_count(definition);
+            let mut id = Hl7Str::NULL;
+            let found = (0..count).any(|i| {
+                hl7_definition_message_at(definition, i, &mut id);
+                to_str(id) == "ADT_A01"
+            });
+            assert!(found);
+        }
+    }
+
+    #[test]
+    fn can_resolve_paths_and_tables() {
+        unsafe {
+            let surname = hl7_resolve_path(c"2.5.1".as_ptr(), c"PID-5.1.1".as_ptr());
+            assert_eq!(to_str(hl7_subfield_description(surname)), "Surname");
+
+            let sex = hl7_resolve_path(c"2.5.1".as_ptr(), c"PID-8".as_ptr());
+            let mut table = 0;
+            assert!(hl7_subfield_table(sex, &mut table));
+            assert_eq!(to_str(hl7_table_value(table, c"F".as_ptr())), "Female");
+            assert!(hl7_table_value(table, c"?".as_ptr()).ptr.is_null());
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter, Result, Write};
+
+/// Just enough of a JSON document model to build FHIR resources with
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Json {
+    Bool(bool),
+    /// A number, kept as it is written so that decimals keep their precision
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    /// An object, whose members are written in order
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    /// An object member, if this is an object and has it
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(k, _)| *k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn write(&self, f: &mut Formatter<'_>, indent: Option<usize>) -> Result {
+        let (open, close, items): (char, char, Vec<(Option<&str>, &Json)>) = match self {
+            Json::Bool(b) => return write!(f, "{b}"),
+            Json::Number(n) => return f.write_str(n),
+            Json::String(s) => return write_string(f, s),
+            Json::Array(items) => ('[', ']', items.iter().map(|v| (None, v)).collect()),
+            Json::Object(members) => (
+                '{',
+                '}',
+                members.iter().map(|(k, v)| (Some(*k), v)).collect(),
+            ),
+        };
+
+        f.write_char(open)?;
+        let inner = indent.map(|indent| indent + 2);
+        for (i, (key, value)) in items.iter().enumerate() {
+            if i > 0 {
+                f.write_char(',')?;
+            }
+            if let Some(inner) = inner {
+                write!(f, "\n{:inner$}", "")?;
+            }
+            if let Some(key) = key {
+                write_string(f, key)?;
+                f.write_str(if inner.is_some() { ": " } else { ":" })?;
+            }
+            value.write(f, inner)?;
+        }
+        if let (Some(indent), false) = (indent, items.is_empty()) {
+            write!(f, "\n{:indent$}", "")?;
+        }
+        f.write_char(close)
+    }
+}
+
+impl Display for Json {
+    /// Writes the JSON compactly, or indented with `{:#}`
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        self.write(f, f.alternate().then_some(0))
+    }
+}
+
+fn write_string(f: &mut Formatter<'_>, s: &str) -> Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// A value that can be an object member, where `None` leaves the member out
+pub(crate) trait Member {
+    fn into_member(self) -> Option<Json>;
+}
+
+impl<T: Into<Json>> Member for T {
+    fn into_member(self) -> Option<Json> {
+        Some(self.into())
+    }
+}
+
+impl<T: Into<Json>> Member for Option<T> {
+    fn into_member(self) -> Option<Json> {
+        self.map(Into::into)
+    }
+}
+
+/// Build a [Json::Object], leaving out members whose values are `None`
+macro_rules! object {
+    ($($key:literal: $value:expr),* $(,)?) => {
+        $crate::fhir::json::Json::Object(
+            [$(($key, $crate::fhir::json::Member::into_member($value))),*]
+                .into_iter()
+                .filter_map(|(key, value)| value.map(|value| (key, value)))
+                .collect(),
+        )
+    };
+}
+pub(crate) use object;
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::String(s.into())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self {
+        Json::String(s)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self {
+        Json::Bool(b)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Self {
+        Json::Number(alloc::format!("{n}"))
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(items: Vec<Json>) -> Self {
+        Json::Array(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn writes_json() {
+        let json = object! {
+            "name": "a \"quoted\"\nline",
+            "skipped": None::<Json>,
+            "items": vec![Json::from(1), Json::from(true), Json::Array(vec![])],
+        };
+        assert_eq!(
+            json.to_string(),
+            r#"{"name":"a \"quoted\"\nline","items":[1,true,[]]}"#
+        );
+        assert_eq!(
+            alloc::format!("{json:#}"),
+            "{\n  \"name\": \"a \\\"quoted\\\"\\nline\",\n  \"items\": [\n    1,\n    true,\n    []\n  ]\n}"
+        );
+    }
+}
@@ -0,0 +1,945 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+use super::json::{object, Json};
+use super::{code_system_url, Resource};
+use crate::convert::{segments, Separators};
+use crate::{get_segment, table_value, try_get_definition, ConvertError, SubField, TableId};
+
+/// How a segment maps to a FHIR resource, following the HL7 v2-to-FHIR implementation guide
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SegmentMapping {
+    /// The segment ID (`PID`)
+    pub segment: &'static str,
+    /// The FHIR resource the segment becomes (`Patient`)
+    pub resource: &'static str,
+    /// The fields that are mapped; any others are dropped
+    pub fields: &'static [FieldMapping],
+}
+
+/// How a segment field maps to an element of a FHIR resource
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct FieldMapping {
+    /// The 1-based field number within the segment
+    pub field: usize,
+    /// The path of the FHIR element the field becomes (`name`, `period.start`)
+    pub element: &'static str,
+    kind: Kind,
+}
+
+/// How a field's value is converted
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Kind {
+    /// Converted according to [DATATYPE_MAPPINGS], by the field's datatype in the version
+    Datatype,
+    /// A `ContactPoint` with the given use, unless the value gives one
+    Telecom(&'static str),
+    /// A date without a time
+    Date,
+    /// A code, translated through a concept map
+    Code(&'static [(&'static str, &'static str)]),
+    /// A `Coding` from a FHIR code system, translated through a concept map of codes and their
+    /// displays
+    Coding(
+        &'static str,
+        &'static [(&'static str, &'static str, &'static str)],
+    ),
+    /// A `Y` / `N` indicator, for a choice element which is only set if the given element of the
+    /// same choice hasn't been
+    BooleanUnless(&'static str),
+    /// A reference range, given as text
+    ReferenceRange,
+    /// An observation value, typed by OBX-2 and with units from OBX-6
+    ObservationValue,
+    /// Used in converting another field
+    Consumed,
+}
+
+const fn map(field: usize, element: &'static str, kind: Kind) -> FieldMapping {
+    FieldMapping {
+        field,
+        element,
+        kind,
+    }
+}
+
+const GENDER: &[(&str, &str)] = &[
+    ("A", "other"),
+    ("F", "female"),
+    ("M", "male"),
+    ("N", "other"),
+    ("O", "other"),
+    ("U", "unknown"),
+];
+
+const OBSERVATION_STATUS: &[(&str, &str)] = &[
+    ("C", "corrected"),
+    ("D", "entered-in-error"),
+    ("F", "final"),
+    ("I", "registered"),
+    ("P", "preliminary"),
+    ("R", "preliminary"),
+    ("S", "preliminary"),
+    ("U", "final"),
+    ("W", "entered-in-error"),
+    ("X", "cancelled"),
+];
+
+/// Patient classes (HL7 table 0004) as v3 `ActCode`s, which `Encounter.class` takes; the
+/// classes without an equivalent aren't mapped
+const ENCOUNTER_CLASS: &[(&str, &str, &str)] = &[
+    ("E", "EMER", "emergency"),
+    ("I", "IMP", "inpatient encounter"),
+    ("O", "AMB", "ambulatory"),
+    ("P", "PRENC", "pre-admission"),
+];
+
+const ACT_CODE: &str = "http://terminology.hl7.org/CodeSystem/v3-ActCode";
+
+/// The segments that are mapped to resources
+pub const SEGMENT_MAPPINGS: &[SegmentMapping] = &[
+    SegmentMapping {
+        segment: "OBX",
+        resource: "Observation",
+        fields: &[
+            map(2, "value[x]", Kind::Consumed),
+            map(3, "code", Kind::Datatype),
+            map(5, "value[x]", Kind::ObservationValue),
+            map(6, "value[x]", Kind::Consumed),
+            map(7, "referenceRange", Kind::ReferenceRange),
+            map(8, "interpretation", Kind::Datatype),
+            map(11, "status", Kind::Code(OBSERVATION_STATUS)),
+            map(14, "effectiveDateTime", Kind::Datatype),
+        ],
+    },
+    SegmentMapping {
+        segment: "PID",
+        resource: "Patient",
+        fields: &[
+            map(2, "identifier", Kind::Datatype),
+            map(3, "identifier", Kind::Datatype),
+            map(4, "identifier", Kind::Datatype),
+            map(5, "name", Kind::Datatype),
+            map(7, "birthDate", Kind::Date),
+            map(8, "gender", Kind::Code(GENDER)),
+            map(11, "address", Kind::Datatype),
+            map(13, "telecom", Kind::Telecom("home")),
+            map(14, "telecom", Kind::Telecom("work")),
+            map(16, "maritalStatus", Kind::Datatype),
+            map(29, "deceasedDateTime", Kind::Datatype),
+            map(
+                30,
+                "deceasedBoolean",
+                Kind::BooleanUnless("deceasedDateTime"),
+            ),
+        ],
+    },
+    SegmentMapping {
+        segment: "PV1",
+        resource: "Encounter",
+        fields: &[
+            map(2, "class", Kind::Coding(ACT_CODE, ENCOUNTER_CLASS)),
+            map(19, "identifier", Kind::Datatype),
+            map(44, "period.start", Kind::Datatype),
+            map(45, "period.end", Kind::Datatype),
+        ],
+    },
+];
+
+/// The FHIR datatype each HL7 datatype is converted to
+pub const DATATYPE_MAPPINGS: &[(&str, &str)] = &[
+    ("AD", "Address"),
+    ("CE", "CodeableConcept"),
+    ("CNE", "CodeableConcept"),
+    ("CWE", "CodeableConcept"),
+    ("CX", "Identifier"),
+    ("DT", "date"),
+    ("DTM", "dateTime"),
+    ("FT", "string"),
+    ("ID", "CodeableConcept"),
+    ("IS", "CodeableConcept"),
+    ("NM", "decimal"),
+    ("PN", "HumanName"),
+    ("ST", "string"),
+    ("TN", "ContactPoint"),
+    ("TS", "dateTime"),
+    ("TX", "string"),
+    ("XAD", "Address"),
+    ("XPN", "HumanName"),
+    ("XTN", "ContactPoint"),
+];
+
+/// How a segment maps to a FHIR resource, if it does
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let mapping = fhir::segment_mapping("PID").expect("PID is mapped");
+/// assert_eq!(mapping.resource, "Patient");
+/// ```
+pub fn segment_mapping(segment: &str) -> Option<&'static SegmentMapping> {
+    SEGMENT_MAPPINGS
+        .iter()
+        .find(|mapping| mapping.segment == segment)
+}
+
+/// The FHIR datatype an HL7 datatype is converted to, if it is supported
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// assert_eq!(fhir::datatype_mapping("XPN"), Some("HumanName"));
+/// assert_eq!(fhir::datatype_mapping("CX"), Some("Identifier"));
+/// ```
+pub fn datatype_mapping(datatype: &str) -> Option<&'static str> {
+    DATATYPE_MAPPINGS
+        .iter()
+        .find(|(v2, _)| *v2 == datatype)
+        .map(|(_, fhir)| *fhir)
+}
+
+/// The fields of a segment, as defined in the given version, which the mapping drops: those
+/// without a field mapping, and those whose datatype has no FHIR mapping
+///
+/// Every field of a segment without a [SegmentMapping] is dropped.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let dropped = fhir::unmapped_fields("2.5.1", "PID").map(|(field, _)| field);
+/// assert!(dropped.collect::<Vec<_>>().contains(&19));
+/// ```
+pub fn unmapped_fields(
+    version: &str,
+    segment: &str,
+) -> impl Iterator<Item = (usize, &'static SubField)> {
+    let mapping = segment_mapping(segment);
+    get_segment(version, segment)
+        .map(|segment| segment.fields)
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| (i + 1, field))
+        .filter(move |(n, field)| {
+            let Some(mapping) = mapping.and_then(|m| m.fields.iter().find(|f| f.field == *n))
+            else {
+                return true;
+            };
+            mapping.kind == Kind::Datatype && datatype_mapping(field.datatype).is_none()
+        })
+}
+
+/// A segment or field with a value that [message_to_fhir] couldn't map
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct UnmappedItem<'a> {
+    /// The 1-based position of the segment within the message
+    pub segment_number: usize,
+    /// The segment ID
+    pub segment: &'a str,
+    /// The 1-based field number, or `None` if the whole segment wasn't mapped
+    pub field: Option<usize>,
+}
+
+impl Display for UnmappedItem<'_> {
+    /// Formats the item as `PID-19`, or just the segment ID
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.segment)?;
+        if let Some(field) = self.field {
+            write!(f, "-{field}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The FHIR resources converted from a message by [message_to_fhir]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FhirConversion<'a> {
+    /// The resources, in the order of the segments they were converted from
+    pub resources: Vec<Resource>,
+    /// Every segment and field with a value that wasn't converted, in message order
+    pub unmapped: Vec<UnmappedItem<'a>>,
+}
+
+impl FhirConversion<'_> {
+    /// Collect the resources into a `collection` Bundle
+    pub fn bundle(&self) -> Resource {
+        let entries = self
+            .resources
+            .iter()
+            .map(|resource| object! { "resource": resource.0.clone() })
+            .collect::<Vec<_>>();
+        Resource(object! {
+            "resourceType": "Bundle",
+            "type": "collection",
+            "entry": entries,
+        })
+    }
+}
+
+/// Convert an ER7-encoded message to FHIR R4 resources, using the [SEGMENT_MAPPINGS] and
+/// [DATATYPE_MAPPINGS] and the definitions of the given version
+///
+/// Each mapped segment becomes a resource with an ID made from its resource type and position
+/// (`patient-2`). Encounters and observations refer to the patient before them, and
+/// observations to the encounter before them. Anything with a value that couldn't be
+/// converted is listed in [FhirConversion::unmapped] rather than silently dropped.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let message = "MSH|^~\\&|A\rPID|1||12345^^^MRN||DOE^JANE||19800229|F";
+/// let conversion = fhir::message_to_fhir(message, "2.5.1").expect("can convert");
+/// let patient = conversion.resources[0].to_string();
+/// assert!(patient.contains(r#""name":[{"family":"DOE","given":["JANE"]}]"#));
+/// assert!(patient.contains(r#""birthDate":"1980-02-29","gender":"female""#));
+/// assert_eq!(conversion.unmapped[0].to_string(), "MSH");
+/// ```
+pub fn message_to_fhir<'a, 'v>(
+    message: &'a str,
+    version: &'v str,
+) -> Result<FhirConversion<'a>, ConvertError<'v>> {
+    let definition = try_get_definition(version)?;
+    let separators = Separators::parse(message).ok_or(ConvertError::MissingHeader)?;
+    let mut conversion = FhirConversion {
+        resources: Vec::new(),
+        unmapped: Vec::new(),
+    };
+    let mut patient = None;
+    let mut encounter = None;
+
+    for segment in segments(message, separators) {
+        let id = segment.id;
+        let unmapped = UnmappedItem {
+            segment_number: segment.number,
+            segment: id,
+            field: None,
+        };
+        let fields = definition.segments.get(id).map(|s| s.fields);
+        let (Some(mapping), Some(fields)) = (segment_mapping(id), fields) else {
+            conversion.unmapped.push(unmapped);
+            continue;
+        };
+
+        let texts = segment.fields().collect::<Vec<_>>();
+        let field = |n: usize| {
+            let (_, text) = texts.iter().find(|(field, _)| *field == n)?;
+            Some(*text).filter(|text| !text.is_empty())
+        };
+        let resource_id = format!(
+            "{}-{}",
+            mapping.resource.to_ascii_lowercase(),
+            segment.number
+        );
+        let mut resource = Builder(vec![
+            ("resourceType", Json::from(mapping.resource)),
+            ("id", Json::from(resource_id.as_str())),
+        ]);
+        for &(n, text) in texts.iter() {
+            if text.is_empty() {
+                continue;
+            }
+            let converter = Converter {
+                separators,
+                definition: fields.get(n - 1),
+            };
+            let converted = match mapping.fields.iter().find(|f| f.field == n) {
+                Some(FieldMapping {
+                    kind: Kind::Consumed,
+                    ..
+                }) => true,
+                Some(FieldMapping {
+                    kind: Kind::BooleanUnless(element),
+                    ..
+                }) if resource.has(element) => true,
+                Some(FieldMapping {
+                    kind: Kind::ObservationValue,
+                    ..
+                }) => converter
+                    .observation_value(text, field(2), field(6))
+                    .map(|(element, value)| resource.set(element, value))
+                    .is_some(),
+                Some(mapping) => converter.field(text, mapping, &mut resource),
+                None => false,
+            };
+            if !converted {
+                conversion.unmapped.push(UnmappedItem {
+                    field: Some(n),
+                    ..unmapped
+                });
+            }
+        }
+
+        let reference = format!("{}/{resource_id}", mapping.resource);
+        match mapping.resource {
+            "Patient" => patient = Some(reference),
+            "Encounter" => {
+                let status = match resource.has_period_end() {
+                    true => "finished",
+                    false => "unknown",
+                };
+                resource.set("status", status.into());
+                if let Some(patient) = &patient {
+                    resource.set("subject", object! { "reference": patient.as_str() });
+                }
+                encounter = Some(reference);
+            }
+            "Observation" => {
+                if !resource.has("status") {
+                    resource.set("status", "unknown".into());
+                }
+                if let Some(patient) = &patient {
+                    resource.set("subject", object! { "reference": patient.as_str() });
+                }
+                if let Some(encounter) = &encounter {
+                    resource.set("encounter", object! { "reference": encounter.as_str() });
+                }
+            }
+            _ => {}
+        }
+        conversion
+            .resources
+            .push(Resource(Json::Object(resource.0)));
+    }
+    Ok(conversion)
+}
+
+/// The members of a resource being built
+struct Builder(Vec<(&'static str, Json)>);
+
+impl Builder {
+    fn has(&self, element: &str) -> bool {
+        self.0.iter().any(|(key, _)| *key == element)
+    }
+
+    fn has_period_end(&self) -> bool {
+        let period = self.0.iter().find(|(key, _)| *key == "period");
+        period.is_some_and(|(_, period)| period.get("end").is_some())
+    }
+
+    fn member<'m>(members: &'m mut [(&'static str, Json)], key: &str) -> Option<&'m mut Json> {
+        members.iter_mut().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    /// Set an element, which may be a path into nested objects like `period.start`
+    fn set(&mut self, element: &'static str, value: Json) {
+        Self::insert(&mut self.0, element, value, false);
+    }
+
+    /// Append to a repeating element
+    fn push(&mut self, element: &'static str, value: Json) {
+        Self::insert(&mut self.0, element, value, true);
+    }
+
+    fn insert(
+        members: &mut Vec<(&'static str, Json)>,
+        element: &'static str,
+        value: Json,
+        repeats: bool,
+    ) {
+        if let Some((first, rest)) = element.split_once('.') {
+            if Self::member(members, first).is_none() {
+                members.push((first, Json::Object(Vec::new())));
+            }
+            if let Some(Json::Object(members)) = Self::member(members, first) {
+                Self::insert(members, rest, value, repeats);
+            }
+            return;
+        }
+        match (Self::member(members, element), repeats) {
+            (Some(Json::Array(items)), true) => items.push(value),
+            (Some(existing), false) => *existing = value,
+            (_, true) => members.push((element, Json::Array(vec![value]))),
+            (None, false) => members.push((element, value)),
+        }
+    }
+}
+
+/// Converts the values of one field
+struct Converter<'d> {
+    separators: Separators,
+    definition: Option<&'d SubField>,
+}
+
+impl Converter<'_> {
+    /// Convert every repetition of a field into the resource, returning whether anything was
+    /// converted
+    fn field(&self, text: &str, mapping: &FieldMapping, resource: &mut Builder) -> bool {
+        let datatype = self.definition.map(|d| d.datatype).unwrap_or_default();
+        let repeats = matches!(
+            datatype_mapping(datatype),
+            Some("HumanName" | "Identifier" | "Address" | "ContactPoint")
+        ) || matches!(mapping.kind, Kind::Telecom(_) | Kind::ReferenceRange)
+            || mapping.element == "interpretation";
+
+        let mut converted = false;
+        for repetition in text.split(self.separators.repetition) {
+            let components = Components::new(repetition, self.separators);
+            let value = match mapping.kind {
+                Kind::Datatype => self.datatype(datatype, &components),
+                Kind::Telecom(default_use) => contact_point(&components, Some(default_use)),
+                Kind::Date => date_time(&components.get(1).unwrap_or_default())
+                    .map(|date| Json::from(date.get(..10).unwrap_or(&date))),
+                Kind::Code(map) => components
+                    .get(1)
+                    .and_then(|code| map.iter().find(|(v2, _)| *v2 == code))
+                    .map(|(_, fhir)| Json::from(*fhir)),
+                Kind::Coding(system, map) => components
+                    .get(1)
+                    .and_then(|code| map.iter().find(|(v2, ..)| *v2 == code))
+                    .map(|(_, code, display)| {
+                        object! { "system": system, "code": *code, "display": *display }
+                    }),
+                Kind::BooleanUnless(_) => match components.get(1).as_deref() {
+                    Some("Y") => Some(true.into()),
+                    Some("N") => Some(false.into()),
+                    _ => None,
+                },
+                Kind::ReferenceRange => components.get(1).map(|range| object! { "text": range }),
+                Kind::ObservationValue | Kind::Consumed => None,
+            };
+            let Some(value) = value else {
+                continue;
+            };
+            converted = true;
+            match repeats {
+                true => resource.push(mapping.element, value),
+                false => {
+                    resource.set(mapping.element, value);
+                    break;
+                }
+            }
+        }
+        converted
+    }
+
+    /// Convert a value according to the FHIR type its datatype maps to
+    fn datatype(&self, datatype: &str, components: &Components<'_>) -> Option<Json> {
+        match datatype_mapping(datatype)? {
+            "HumanName" => human_name(components),
+            "Identifier" => identifier(components),
+            "Address" => address(components),
+            "ContactPoint" => contact_point(components, None),
+            "CodeableConcept" if matches!(datatype, "ID" | "IS") => {
+                let table = self.definition.and_then(|d| d.table)?;
+                let code = components.get(1)?;
+                Some(object! {
+                    "coding": vec![coding(u16::try_from(table).ok()?, &code)],
+                })
+            }
+            "CodeableConcept" => codeable_concept(components),
+            "dateTime" => date_time(&components.get(1)?).map(Json::from),
+            "date" => date_time(&components.get(1)?)
+                .map(|date| Json::from(date.get(..10).unwrap_or(&date))),
+            "decimal" => decimal(&components.get(1)?),
+            _ => components.text().map(Json::from),
+        }
+    }
+
+    /// Convert OBX-5 to the `value[x]` element its value type (OBX-2) calls for
+    fn observation_value(
+        &self,
+        text: &str,
+        value_type: Option<&str>,
+        units: Option<&str>,
+    ) -> Option<(&'static str, Json)> {
+        // only the first repetition can be represented
+        let text = text.split(self.separators.repetition).next()?;
+        let components = Components::new(text, self.separators);
+        match value_type.unwrap_or_default() {
+            "NM" => {
+                let mut quantity = vec![("value", decimal(&components.get(1)?)?)];
+                if let Some(units) = units.map(|units| Components::new(units, self.separators)) {
+                    let unit = units.get(2).or_else(|| units.get(1));
+                    let system = units.get(3).and_then(|system| system_url(&system));
+                    quantity.extend(
+                        [("unit", unit), ("system", system), ("code", units.get(1))]
+                            .into_iter()
+                            .filter_map(|(key, value)| Some((key, Json::from(value?)))),
+                    );
+                }
+                Some(("valueQuantity", Json::Object(quantity)))
+            }
+            "CE" | "CWE" | "CNE" => {
+                codeable_concept(&components).map(|value| ("valueCodeableConcept", value))
+            }
+            "DT" | "DTM" | "TS" => {
+                date_time(&components.get(1)?).map(|value| ("valueDateTime", Json::from(value)))
+            }
+            _ => Components::new(text, self.separators)
+                .text()
+                .map(|value| ("valueString", Json::from(value))),
+        }
+    }
+}
+
+/// The components of a repetition, split on demand
+struct Components<'a> {
+    text: &'a str,
+    separators: Separators,
+}
+
+impl<'a> Components<'a> {
+    fn new(text: &'a str, separators: Separators) -> Self {
+        Components { text, separators }
+    }
+
+    /// The first sub-component of the 1-based component `n`, unescaped, if it has a value
+    fn get(&self, n: usize) -> Option<String> {
+        self.subcomponent(n, 1)
+    }
+
+    fn subcomponent(&self, n: usize, s: usize) -> Option<String> {
+        let component = self.text.split(self.separators.component).nth(n - 1)?;
+        let subcomponent = component.split(self.separators.subcomponent).nth(s - 1)?;
+        Some(unescape(subcomponent, self.separators)).filter(|value| !value.is_empty())
+    }
+
+    /// The whole value, unescaped, for text datatypes
+    fn text(&self) -> Option<String> {
+        Some(unescape(self.text, self.separators)).filter(|value| !value.is_empty())
+    }
+}
+
+/// Replace the escape sequences for the delimiters; other escape sequences are left as they are
+fn unescape(text: &str, separators: Separators) -> String {
+    let escape = separators.escape;
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(escape) {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + escape.len_utf8()..];
+        let Some(end) = after.find(escape) else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        match &after[..end] {
+            "F" => out.push(separators.field),
+            "S" => out.push(separators.component),
+            "T" => out.push(separators.subcomponent),
+            "R" => out.push(separators.repetition),
+            "E" => out.push(escape),
+            sequence => {
+                out.push(escape);
+                out.push_str(sequence);
+                out.push(escape);
+            }
+        }
+        rest = &after[end + escape.len_utf8()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn human_name(c: &Components<'_>) -> Option<Json> {
+    let given = [c.get(2), c.get(3)].into_iter().flatten();
+    let given = given.map(Json::from).collect::<Vec<_>>();
+    let name_use = match c.get(7).as_deref() {
+        Some("L") => Some("official"),
+        Some("D") => Some("usual"),
+        Some("M") => Some("maiden"),
+        Some("N") => Some("nickname"),
+        _ => None,
+    };
+    let name = object! {
+        "use": name_use,
+        "family": c.get(1),
+        "given": (!given.is_empty()).then_some(given),
+        "suffix": c.get(4).map(|suffix| vec![Json::from(suffix)]),
+        "prefix": c.get(5).map(|prefix| vec![Json::from(prefix)]),
+    };
+    non_empty(name)
+}
+
+fn identifier(c: &Components<'_>) -> Option<Json> {
+    let identifier_type = c
+        .get(5)
+        .map(|code| object! { "coding": vec![coding(203, &code)] });
+    Some(object! {
+        "type": identifier_type,
+        "value": c.get(1)?,
+        "assigner": c.get(4).map(|assigner| object! { "display": assigner }),
+    })
+}
+
+fn address(c: &Components<'_>) -> Option<Json> {
+    let lines = [c.get(1), c.get(2)].into_iter().flatten();
+    let lines = lines.map(Json::from).collect::<Vec<_>>();
+    let address_use = match c.get(7).as_deref() {
+        Some("H") => Some("home"),
+        Some("B" | "O") => Some("work"),
+        Some("C") => Some("temp"),
+        _ => None,
+    };
+    non_empty(object! {
+        "use": address_use,
+        "line": (!lines.is_empty()).then_some(lines),
+        "city": c.get(3),
+        "state": c.get(4),
+        "postalCode": c.get(5),
+        "country": c.get(6),
+    })
+}
+
+fn contact_point(c: &Components<'_>, default_use: Option<&'static str>) -> Option<Json> {
+    let (value, system) = match (c.get(1), c.get(4)) {
+        (_, Some(email)) => (email, Some("email")),
+        (Some(number), None) => (number, None),
+        (None, None) => (c.get(7)?, None),
+    };
+    let system = system.or(match c.get(3).as_deref() {
+        Some("PH" | "CP") => Some("phone"),
+        Some("FX") => Some("fax"),
+        Some("BP") => Some("pager"),
+        Some("Internet" | "X.400") => Some("email"),
+        _ => None,
+    });
+    let contact_use = match (c.get(3).as_deref(), c.get(2).as_deref()) {
+        (Some("CP"), _) => Some("mobile"),
+        (_, Some("PRN" | "ORN" | "VHN")) => Some("home"),
+        (_, Some("WPN")) => Some("work"),
+        _ => default_use,
+    };
+    Some(object! {
+        "system": system,
+        "value": value,
+        "use": contact_use,
+    })
+}
+
+fn codeable_concept(c: &Components<'_>) -> Option<Json> {
+    let codings = [(1, 2, 3), (4, 5, 6)]
+        .into_iter()
+        .filter_map(|(code, display, system)| {
+            let (code, display) = (c.get(code), c.get(display));
+            if code.is_none() && display.is_none() {
+                return None;
+            }
+            Some(object! {
+                "system": c.get(system).and_then(|system| system_url(&system)),
+                "code": code,
+                "display": display,
+            })
+        })
+        .collect::<Vec<_>>();
+    non_empty(object! {
+        "coding": (!codings.is_empty()).then_some(codings),
+        "text": c.get(9),
+    })
+}
+
+/// A `Coding` for a code from an HL7 table, with its description if the table is compiled in
+fn coding(table: u16, code: &str) -> Json {
+    object! {
+        "system": code_system_url(TableId(table)),
+        "code": code,
+        "display": table_value(table, code),
+    }
+}
+
+/// The FHIR system URL for an HL7 coding system name, if it is one FHIR knows
+fn system_url(system: &str) -> Option<String> {
+    match system {
+        "LN" => Some("http://loinc.org".into()),
+        "SCT" | "SNM" | "SNM3" => Some("http://snomed.info/sct".into()),
+        "UCUM" => Some("http://unitsofmeasure.org".into()),
+        "I10" => Some("http://hl7.org/fhir/sid/icd-10".into()),
+        "I9C" => Some("http://hl7.org/fhir/sid/icd-9-cm".into()),
+        system => {
+            let table = system.strip_prefix("HL7")?.parse::<u16>().ok()?;
+            Some(code_system_url(TableId(table)))
+        }
+    }
+}
+
+/// Convert an HL7 date/time (`YYYY[MM[DD[HHMM[SS[.S]]]]][+/-ZZZZ]`) to a FHIR `dateTime`.
+/// FHIR requires a time zone with any time, so times without one are dropped, keeping the
+/// date.
+fn date_time(value: &str) -> Option<String> {
+    let (value, zone) = match value.find(['+', '-']) {
+        Some(i) => (&value[..i], Some(&value[i..])),
+        None => (value, None),
+    };
+    // the sign is ASCII, so the zone can be split after it
+    if zone.is_some_and(|zone| zone.len() != 5 || !zone[1..].bytes().all(|b| b.is_ascii_digit())) {
+        return None;
+    }
+    let (digits, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if !digits.bytes().all(|b| b.is_ascii_digit()) || !fraction.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let part = |range: core::ops::Range<usize>| digits.get(range).unwrap_or("00");
+    let date = match digits.len() {
+        4 => return Some(digits.into()),
+        6 => return Some(format!("{}-{}", &digits[..4], &digits[4..6])),
+        8 | 10 | 12 | 14 => format!("{}-{}-{}", &digits[..4], &digits[4..6], &digits[6..8]),
+        _ => return None,
+    };
+    let zone = match zone {
+        Some(zone) if digits.len() > 8 => zone,
+        _ => return Some(date),
+    };
+    let mut time = format!("{date}T{}:{}:{}", part(8..10), part(10..12), part(12..14));
+    if !fraction.is_empty() && digits.len() == 14 {
+        time.push('.');
+        time.push_str(fraction);
+    }
+    Some(format!("{time}{}:{}", &zone[..3], &zone[3..]))
+}
+
+fn decimal(value: &str) -> Option<Json> {
+    let sign = if value.starts_with('-') { "-" } else { "" };
+    let digits = value.strip_prefix(['+', '-']).unwrap_or(value);
+    let (whole, fraction) = match digits.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (digits, None),
+    };
+    let valid = !whole.is_empty()
+        && fraction != Some("")
+        && whole
+            .bytes()
+            .chain(fraction.unwrap_or_default().bytes())
+            .all(|b| b.is_ascii_digit());
+    if !valid {
+        return None;
+    }
+    // JSON numbers don't allow leading zeros
+    let whole = match whole.trim_start_matches('0') {
+        "" => "0",
+        whole => whole,
+    };
+    Some(Json::Number(match fraction {
+        Some(fraction) => format!("{sign}{whole}.{fraction}"),
+        None => format!("{sign}{whole}"),
+    }))
+}
+
+/// An object, if it has any members
+fn non_empty(json: Json) -> Option<Json> {
+    match &json {
+        Json::Object(members) if members.is_empty() => None,
+        _ => Some(json),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn mappings_match_the_definitions() {
+        for mapping in SEGMENT_MAPPINGS {
+            let segment = get_segment("2.5.1", mapping.segment).expect("segment exists");
+            // trimmed-down definitions may not have every mapped field
+            for field in mapping.fields {
+                let Some(definition) = segment.fields.get(field.field - 1) else {
+                    continue;
+                };
+                if field.kind == Kind::Datatype {
+                    assert!(
+                        datatype_mapping(definition.datatype).is_some(),
+                        "{}-{} is a {} which isn't mapped",
+                        mapping.segment,
+                        field.field,
+                        definition.datatype
+                    );
+                }
+            }
+        }
+        let pid = unmapped_fields("2.5.1", "PID")
+            .map(|(n, _)| n)
+            .collect::<Vec<_>>();
+        assert!(pid.contains(&1) && !pid.contains(&5));
+        assert_eq!(unmapped_fields("2.5.1", "MSA").count(), 2);
+    }
+
+    #[test]
+    fn converts_messages() {
+        let message = "MSH|^~\\&|A\r\
+            PID|1||123^^^MRN^MR||DOE^JANE^Q^^DR^^L||198002291230+0100|F|||1 MAIN\\S\\2^^TOWN^ON^A1A^CA^H||555-1234^PRN^PH~^NET^Internet^jane@example.com|||||||||||||||||Y\r\
+            PV1|1|I\r\
+            OBX|1|NM|718-7^Hemoglobin^LN||13.5|g/dL^^UCUM|12-16|H|||F\r\
+            ZPI|1";
+        let conversion = message_to_fhir(message, "2.5.1").expect("can convert");
+        let resources = conversion
+            .resources
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(resources.len(), 3);
+        let patient = &resources[0];
+        assert!(patient.starts_with(r#"{"resourceType":"Patient","id":"patient-2","#));
+        assert!(patient.contains(
+            r#""coding":[{"system":"http://terminology.hl7.org/CodeSystem/v2-0203","code":"MR""#
+        ));
+        assert!(patient.contains(r#""value":"123","assigner":{"display":"MRN"}}],"#));
+        assert!(patient.contains(
+            r#""name":[{"use":"official","family":"DOE","given":["JANE","Q"],"prefix":["DR"]}],"#
+        ));
+        assert!(patient.contains(r#""birthDate":"1980-02-29","gender":"female","#));
+        assert!(patient.contains(
+            r#""address":[{"use":"home","line":["1 MAIN^2"],"city":"TOWN","state":"ON","postalCode":"A1A","country":"CA"}],"#
+        ));
+        assert!(patient.contains(
+            r#""telecom":[{"system":"phone","value":"555-1234","use":"home"},{"system":"email","value":"jane@example.com","use":"home"}],"#
+        ));
+        assert!(patient.ends_with(r#""deceasedBoolean":true}"#));
+        let died = "MSH|^~\\&|A\rPID|1||123||||||||||||||||||||||||||200401011230+0100|Y\rPV1|1|N";
+        let died = message_to_fhir(died, "2.5.1").expect("can convert");
+        assert!(died.resources[0]
+            .to_string()
+            .ends_with(r#""deceasedDateTime":"2004-01-01T12:30:00+01:00"}"#));
+        let unmapped = died.unmapped.iter().map(ToString::to_string);
+        assert!(unmapped.eq(["MSH", "PID-1", "PV1-1", "PV1-2"]));
+        assert!(resources[1].contains(
+            r#""class":{"system":"http://terminology.hl7.org/CodeSystem/v3-ActCode","code":"IMP","display":"inpatient encounter"}"#
+        ));
+        assert!(resources[1]
+            .ends_with(r#""status":"unknown","subject":{"reference":"Patient/patient-2"}}"#));
+        assert!(resources[2].contains(r#""valueQuantity":{"value":13.5,"unit":"g/dL","system":"http://unitsofmeasure.org","code":"g/dL"}"#));
+        assert!(resources[2].contains(r#""referenceRange":[{"text":"12-16"}]"#));
+        assert!(resources[2].contains(r#""status":"final""#));
+        assert!(resources[2].ends_with(r#""encounter":{"reference":"Encounter/encounter-3"}}"#));
+
+        let unmapped = conversion
+            .unmapped
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(unmapped, ["MSH", "PID-1", "PV1-1", "OBX-1", "ZPI"]);
+
+        let bundle = conversion.bundle().to_string();
+        assert!(bundle.starts_with(r#"{"resourceType":"Bundle","type":"collection","entry":[{"resource":{"resourceType":"Patient""#));
+    }
+
+    #[test]
+    fn converts_dates() {
+        assert_eq!(date_time("1980").as_deref(), Some("1980"));
+        assert_eq!(date_time("198002").as_deref(), Some("1980-02"));
+        assert_eq!(date_time("198002291230").as_deref(), Some("1980-02-29"));
+        assert_eq!(
+            date_time("19800229123015.25-0500").as_deref(),
+            Some("1980-02-29T12:30:15.25-05:00")
+        );
+        assert_eq!(
+            date_time("1980022912-0500").as_deref(),
+            Some("1980-02-29T12:00:00-05:00")
+        );
+        assert_eq!(date_time("19800229-05"), None);
+        assert_eq!(date_time("200401011230+0é0"), None);
+        assert_eq!(date_time("garbage"), None);
+        assert_eq!(decimal("+13.50"), Some(Json::Number("13.50".into())));
+        assert_eq!(decimal("007.5"), Some(Json::Number("7.5".into())));
+        assert_eq!(decimal("-00.25"), Some(Json::Number("-0.25".into())));
+        assert_eq!(decimal("000"), Some(Json::Number("0".into())));
+        assert_eq!(decimal("<5"), None);
+    }
+}
@@ -0,0 +1,40 @@
+//! Conversions from HL7 v2 definitions, tables and messages to FHIR R4 resources
+//!
+//! Resources are returned as [Resource]s, which display as JSON: compactly with `{}`, or
+//! indented with `{:#}`.
+
+use core::fmt::Display;
+
+mod json;
+mod mapping;
+mod terminology;
+
+use json::Json;
+pub use mapping::*;
+pub use terminology::*;
+
+/// A FHIR R4 resource
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Resource(Json);
+
+impl Resource {
+    /// The type of the resource, such as `CodeSystem`
+    pub fn resource_type(&self) -> &str {
+        self.0
+            .get("resourceType")
+            .and_then(Json::as_str)
+            .unwrap_or_default()
+    }
+
+    /// The logical ID of the resource, if it has one
+    pub fn id(&self) -> Option<&str> {
+        self.0.get("id").and_then(Json::as_str)
+    }
+}
+
+impl Display for Resource {
+    /// Writes the resource as JSON, indented if formatted with `{:#}`
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
@@ -0,0 +1,210 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::json::{object, Json};
+use super::Resource;
+use crate::{tables, Table, TableId, TableType, ValueStatus};
+
+/// The canonical URL of the FHIR `CodeSystem` for an HL7 table, such as
+/// `http://terminology.hl7.org/CodeSystem/v2-0001`
+pub fn code_system_url(table: TableId) -> String {
+    format!("http://terminology.hl7.org/CodeSystem/v2-{table}")
+}
+
+/// The canonical URL of the FHIR `ValueSet` for an HL7 table, such as
+/// `http://terminology.hl7.org/ValueSet/v2-0001`
+pub fn value_set_url(table: TableId) -> String {
+    format!("http://terminology.hl7.org/ValueSet/v2-{table}")
+}
+
+/// A computable name for a table, such as `AdministrativeSex`, falling back to `V2Table0001`
+/// if the description doesn't start with a letter
+fn computable_name(table: &Table) -> String {
+    let name = table
+        .description()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect::<String>();
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => name,
+        _ => format!("V2Table{}", table.id()),
+    }
+}
+
+/// The FHIR concept status of a table value; active values don't carry one
+fn concept_status(status: ValueStatus) -> Option<&'static str> {
+    match status {
+        ValueStatus::Active => None,
+        ValueStatus::BackwardCompatibility | ValueStatus::Deprecated => Some("deprecated"),
+        ValueStatus::Withdrawn => Some("retired"),
+    }
+}
+
+/// Export a table as a FHIR R4 `CodeSystem`, with every value as a concept in code order
+///
+/// HL7-defined tables are `complete` code systems, while user-defined and unclassified tables
+/// are only an `example` of the codes sites use. Values which are deprecated, kept for backwards
+/// compatibility or withdrawn carry a `status` property, and usage notes become the concept's
+/// definition.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let table = get_table(1).expect("table 1 exists");
+/// let code_system = fhir::code_system(&table);
+/// assert_eq!(code_system.id(), Some("v2-0001"));
+/// let json = code_system.to_string();
+/// assert!(json.contains(r#""url":"http://terminology.hl7.org/CodeSystem/v2-0001""#));
+/// assert!(json.contains(r#"{"code":"F","display":"Female"}"#));
+/// ```
+pub fn code_system(table: &Table) -> Resource {
+    let mut entries = table.entries().collect::<Vec<_>>();
+    entries.sort_by_key(|(code, _)| *code);
+    let has_statuses = entries
+        .iter()
+        .any(|(_, value)| concept_status(value.status).is_some());
+    let concepts = entries
+        .into_iter()
+        .map(|(code, value)| {
+            let property = concept_status(value.status)
+                .map(|status| Json::from(vec![object! { "code": "status", "valueCode": status }]));
+            object! {
+                "code": code,
+                "display": value.description,
+                "definition": value.notes,
+                "property": property,
+            }
+        })
+        .collect::<Vec<_>>();
+    let content = match table.table_type() {
+        Some(TableType::Hl7) => "complete",
+        _ => "example",
+    };
+    let properties = has_statuses.then(|| {
+        Json::from(vec![object! {
+            "code": "status",
+            "uri": "http://hl7.org/fhir/concept-properties#status",
+            "type": "code",
+        }])
+    });
+
+    Resource(object! {
+        "resourceType": "CodeSystem",
+        "id": format!("v2-{}", table.id()),
+        "url": code_system_url(table.id()),
+        "name": computable_name(table),
+        "title": table.description(),
+        "status": "active",
+        "experimental": false,
+        "description": table.table_type().map(|table_type| format!("HL7 v2 {table_type} table {}", table.id())),
+        "caseSensitive": true,
+        "valueSet": value_set_url(table.id()),
+        "content": content,
+        "count": concepts.len(),
+        "property": properties,
+        "concept": concepts,
+    })
+}
+
+/// Export a table as a FHIR R4 `ValueSet` including every code of its [code_system]
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let table = get_table(1).expect("table 1 exists");
+/// let value_set = fhir::value_set(&table);
+/// assert_eq!(value_set.resource_type(), "ValueSet");
+/// assert!(value_set
+///     .to_string()
+///     .contains(r#""include":[{"system":"http://terminology.hl7.org/CodeSystem/v2-0001"}]"#));
+/// ```
+pub fn value_set(table: &Table) -> Resource {
+    Resource(object! {
+        "resourceType": "ValueSet",
+        "id": format!("v2-{}", table.id()),
+        "url": value_set_url(table.id()),
+        "name": computable_name(table),
+        "title": table.description(),
+        "status": "active",
+        "experimental": false,
+        "compose": object! {
+            "include": vec![object! { "system": code_system_url(table.id()) }],
+        },
+    })
+}
+
+/// Export every table compiled into the library as a `CodeSystem` followed by its `ValueSet`,
+/// in table number order
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// for resource in fhir::table_resources() {
+///     let file = format!("{}-{}.json", resource.resource_type(), resource.id().unwrap());
+///     let json = format!("{resource:#}");
+///     // std::fs::write(file, json)?;
+///     # let _ = (file, json);
+/// }
+/// ```
+pub fn table_resources() -> impl Iterator<Item = Resource> {
+    let mut tables = tables().collect::<Vec<_>>();
+    tables.sort_by_key(Table::id);
+    tables
+        .into_iter()
+        .flat_map(|table| [code_system(&table), value_set(&table)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_table;
+    use alloc::string::ToString;
+
+    #[test]
+    fn exports_code_systems() {
+        let table = get_table(1).expect("table 1 exists");
+        let code_system = code_system(&table);
+        assert_eq!(code_system.resource_type(), "CodeSystem");
+        let json = code_system.to_string();
+        assert!(json.contains(r#""name":"AdministrativeSex","title":"Administrative Sex""#));
+        assert!(json.contains(r#""content":"example""#));
+        assert!(json.contains(&format!(r#""count":{}"#, table.len())));
+
+        let table = get_table(3).expect("table 3 exists");
+        let json = super::code_system(&table).to_string();
+        assert!(json.contains(r#""content":"complete""#));
+        assert!(json.contains(r#""property":[{"code":"status","valueCode":"deprecated"}]"#));
+        let a01 = json.find(r#""code":"A01""#).expect("A01 is a concept");
+        let a02 = json.find(r#""code":"A02""#).expect("A02 is a concept");
+        assert!(a01 < a02);
+
+        let table = get_table(52).expect("table 52 exists");
+        assert_eq!(table.table_type(), None);
+        let json = super::code_system(&table).to_string();
+        assert!(json.contains(r#""content":"example""#));
+    }
+
+    #[test]
+    fn exports_every_table() {
+        let resources = table_resources().collect::<Vec<_>>();
+        assert_eq!(resources.len(), tables().count() * 2);
+        assert_eq!(resources[0].resource_type(), "CodeSystem");
+        assert_eq!(resources[1].resource_type(), "ValueSet");
+        assert_eq!(resources[0].id(), resources[1].id());
+        assert!(resources
+            .windows(2)
+            .all(|pair| pair[0].id() <= pair[1].id()));
+    }
+}
@@ -0,0 +1,193 @@
+use core::fmt::Display;
+
+use crate::{get_definition, SubField};
+
+/// A primitive component or sub-component of a datatype, along with its position
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct FlatComponent {
+    /// The 1-based component number within the datatype
+    pub component: usize,
+    /// The 1-based sub-component number within the component, if the component is itself a
+    /// composite
+    pub subcomponent: Option<usize>,
+    /// The definition of the primitive component or sub-component
+    pub definition: &'static SubField,
+    /// The definition of the component; the same as `definition` if there is no sub-component
+    pub component_definition: &'static SubField,
+    /// Whether this sub-component is itself a composite, nested deeper than HL7's encoding can
+    /// represent; it is given as a primitive rather than expanded any further
+    pub illegal_nesting: bool,
+}
+
+impl FlatComponent {
+    /// Whether this is a sub-component of a composite component
+    pub fn is_subcomponent(&self) -> bool {
+        self.subcomponent.is_some()
+    }
+}
+
+impl Display for FlatComponent {
+    /// Formats the position as `component` or `component.subcomponent`, such as `1.1`
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.component)?;
+        if let Some(subcomponent) = self.subcomponent {
+            write!(f, ".{subcomponent}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The error returned when a datatype can't be flattened
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FlattenError {
+    /// The version or datatype doesn't exist
+    UnknownDatatype,
+}
+
+impl Display for FlattenError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FlattenError::UnknownDatatype => write!(f, "unknown datatype"),
+        }
+    }
+}
+
+impl core::error::Error for FlattenError {}
+
+/// The components of a sub-field's datatype, or `None` if it is a primitive (or unknown)
+fn composite(subfield: &SubField) -> Option<&'static [SubField]> {
+    subfield
+        .datatype_definition()
+        .map(|field| field.subfields)
+        .filter(|subfields| !subfields.is_empty())
+}
+
+/// Expand a datatype into its primitive components and sub-components, in order
+///
+/// Components whose datatype is a composite are replaced by their sub-components. Primitive
+/// datatypes have no components, so flatten to nothing.
+///
+/// A sub-component whose datatype is itself a composite (such as `XPN.10.1`, a `TS` in 2.5.1)
+/// can't be encoded any deeper, so it is given as a primitive with
+/// [illegal_nesting](FlatComponent::illegal_nesting) set.
+///
+/// Returns an error if the datatype doesn't exist in the version.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let xpn = flatten_datatype("2.5.1", "XPN").expect("can flatten XPN");
+/// let surname = xpn.clone().next().expect("XPN has components");
+/// assert_eq!(surname.to_string(), "1.1");
+/// assert_eq!(surname.definition.description, "Surname");
+/// assert_eq!(surname.component_definition.description, "Family Name");
+/// ```
+pub fn flatten_datatype(version: &str, datatype: &str) -> Result<FlatComponents, FlattenError> {
+    let components = get_definition(version)
+        .ok_or(FlattenError::UnknownDatatype)?
+        .fields
+        .get(datatype)
+        .ok_or(FlattenError::UnknownDatatype)?
+        .subfields;
+
+    Ok(FlatComponents {
+        components,
+        next_component: 0,
+        subcomponents: None,
+    })
+}
+
+/// An iterator over the primitive components of a datatype; see [flatten_datatype]
+#[derive(Clone, Debug)]
+pub struct FlatComponents {
+    components: &'static [SubField],
+    next_component: usize,
+    /// The composite component currently being expanded, its sub-components, and the index of
+    /// the next sub-component
+    subcomponents: Option<(&'static SubField, &'static [SubField], usize)>,
+}
+
+impl Iterator for FlatComponents {
+    type Item = FlatComponent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((component, subcomponents, next)) = &mut self.subcomponents {
+                if let Some(subcomponent) = subcomponents.get(*next) {
+                    *next += 1;
+                    return Some(FlatComponent {
+                        component: self.next_component,
+                        subcomponent: Some(*next),
+                        definition: subcomponent,
+                        component_definition: component,
+                        illegal_nesting: composite(subcomponent).is_some(),
+                    });
+                }
+                self.subcomponents = None;
+            }
+
+            let component = self.components.get(self.next_component)?;
+            self.next_component += 1;
+            match composite(component) {
+                Some(subcomponents) => self.subcomponents = Some((component, subcomponents, 0)),
+                None => {
+                    return Some(FlatComponent {
+                        component: self.next_component,
+                        subcomponent: None,
+                        definition: component,
+                        component_definition: component,
+                        illegal_nesting: false,
+                    })
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_flatten_datatypes() {
+        let cx = flatten_datatype("2.5.1", "CX")
+            .expect("can flatten CX")
+            .map(|c| (c.to_string(), c.definition.datatype))
+            .collect::<Vec<_>>();
+        assert_eq!(cx[0], ("1".to_string(), "ST"));
+        assert!(cx.contains(&("4.1".to_string(), "IS")));
+        assert!(cx.contains(&("4.2".to_string(), "ST")));
+
+        let ts = flatten_datatype("2.5.1", "TS").expect("can flatten TS");
+        assert!(ts.clone().all(|c| !c.is_subcomponent()));
+        assert_eq!(ts.count(), 2);
+
+        assert_eq!(
+            flatten_datatype("2.5.1", "ST")
+                .expect("can flatten ST")
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn flags_illegal_nesting() {
+        let tq = flatten_datatype("2.5.1", "TQ")
+            .expect("can flatten TQ")
+            .collect::<Vec<_>>();
+        let nested = tq
+            .iter()
+            .filter(|c| c.illegal_nesting)
+            .map(|c| (c.to_string(), c.definition.datatype))
+            .collect::<Vec<_>>();
+        assert_eq!(nested[0], ("1.2".to_string(), "CE"));
+        assert!(tq.iter().any(|c| !c.illegal_nesting && c.is_subcomponent()));
+        assert!(tq.iter().any(|c| c.component > 1));
+
+        assert_eq!(
+            flatten_datatype("2.5.1", "ZZZ").err(),
+            Some(FlattenError::UnknownDatatype)
+        );
+    }
+}
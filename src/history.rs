@@ -0,0 +1,282 @@
+use core::fmt::Display;
+
+use crate::path::split_path;
+use crate::{definitions, Definition, FieldOptionality, FieldPath, SubField};
+
+/// The segment field or datatype component whose history [field_history] traces
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum HistorySubject<'a> {
+    /// A field of a segment, such as `PID-13`, or a component or sub-component of one, such as
+    /// `PID-5.1`
+    Path(FieldPath<'a>),
+    /// A component of a datatype, such as `CWE.2`
+    Component {
+        /// The datatype ID (`CWE`)
+        datatype: &'a str,
+        /// The 1-based component number within the datatype
+        component: usize,
+    },
+}
+
+impl<'a> HistorySubject<'a> {
+    /// Parse a subject written as `PID-13` or `PID-5.1` (a path within a segment, as
+    /// [FieldPath::parse] reads it) or `CWE.2` (a datatype component), the same notation as
+    /// `assets/field_lengths.json`
+    ///
+    /// Unlike [FieldPath::parse], the separator after the ID matters: `PID.13` would be
+    /// component 13 of a datatype called `PID`. Returns `None` if the subject is malformed or any
+    /// of its numbers are `0`.
+    pub fn parse(subject: &'a str) -> Option<HistorySubject<'a>> {
+        match split_path(subject)? {
+            ('-', _) => FieldPath::parse(subject).map(HistorySubject::Path),
+            (
+                _,
+                FieldPath {
+                    segment: datatype,
+                    field: component,
+                    component: None,
+                    subcomponent: None,
+                },
+            ) => Some(HistorySubject::Component {
+                datatype,
+                component,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The subject's definition in a version, if it exists there
+    fn resolve(&self, version: &str, definition: &'static Definition) -> Option<&'static SubField> {
+        match *self {
+            HistorySubject::Path(path) => path.resolve(version).map(|path| path.target()),
+            HistorySubject::Component {
+                datatype,
+                component,
+            } => definition
+                .fields
+                .get(datatype)?
+                .subfields
+                .get(component - 1),
+        }
+    }
+}
+
+impl Display for HistorySubject<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HistorySubject::Path(path) => path.fmt(f),
+            HistorySubject::Component {
+                datatype,
+                component,
+            } => write!(f, "{datatype}.{component}"),
+        }
+    }
+}
+
+/// Which parts of a definition changed from the previous version
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct FieldChanges {
+    /// The datatype changed
+    pub datatype: bool,
+    /// The maximum length, conformance length or truncation changed
+    pub length: bool,
+    /// The optionality changed
+    pub optionality: bool,
+    /// The repeatability changed
+    pub repeatability: bool,
+    /// The table changed
+    pub table: bool,
+    /// The description changed
+    pub description: bool,
+}
+
+impl FieldChanges {
+    fn between(previous: &SubField, current: &SubField) -> FieldChanges {
+        FieldChanges {
+            datatype: previous.datatype != current.datatype,
+            length: previous.max_length != current.max_length
+                || previous.conformance_length != current.conformance_length
+                || previous.truncation != current.truncation,
+            optionality: previous.optionality != current.optionality,
+            repeatability: previous.repeatability != current.repeatability,
+            table: previous.table != current.table,
+            description: previous.description != current.description,
+        }
+    }
+
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        *self == FieldChanges::default()
+    }
+}
+
+/// A milestone in the life of a field or component
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum HistoryEvent {
+    /// The item appears for the first time, or again after being withdrawn
+    Introduced,
+    /// The item became [FieldOptionality::BackwardCompatibility]
+    Deprecated,
+    /// The item no longer exists
+    Withdrawn,
+}
+
+impl Display for HistoryEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HistoryEvent::Introduced => write!(f, "introduced"),
+            HistoryEvent::Deprecated => write!(f, "deprecated"),
+            HistoryEvent::Withdrawn => write!(f, "withdrawn"),
+        }
+    }
+}
+
+/// A field or component's definition in one version, compared to the version before it
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct HistoryEntry {
+    /// The version
+    pub version: &'static str,
+    /// The definition in this version, or `None` if it doesn't exist in this version
+    pub definition: Option<&'static SubField>,
+    /// What changed since the previous version; empty if the item doesn't exist in both
+    pub changes: FieldChanges,
+    /// The milestone this version marks, if any
+    pub event: Option<HistoryEvent>,
+}
+
+/// Trace a segment field or datatype component through every compiled-in version, oldest
+/// first, noting what changed in each version and when it was introduced, deprecated or
+/// withdrawn
+///
+/// Every compiled-in version gets an entry, including those the item doesn't exist in. Only
+/// compiled-in versions are compared, so with some versions disabled an item is "introduced"
+/// in the oldest enabled version that has it.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let subject = HistorySubject::parse("PID-13").expect("can parse subject");
+/// let mut history = field_history(subject);
+/// let first = history.next().expect("PID-13 has a history");
+/// assert_eq!(first.event, Some(HistoryEvent::Introduced));
+/// for entry in history.filter(|entry| entry.changes.datatype) {
+///     let datatype = entry.definition.expect("changed items exist").datatype;
+///     println!("PID-13 became {datatype} in {}", entry.version);
+/// }
+/// ```
+pub fn field_history(subject: HistorySubject<'_>) -> impl Iterator<Item = HistoryEntry> + '_ {
+    definitions().scan(
+        None,
+        move |previous: &mut Option<&'static SubField>, (version, definition)| {
+            let current = subject.resolve(version, definition);
+            let (changes, event) = match (*previous, current) {
+                (None, None) => (FieldChanges::default(), None),
+                (None, Some(_)) => (FieldChanges::default(), Some(HistoryEvent::Introduced)),
+                (Some(_), None) => (FieldChanges::default(), Some(HistoryEvent::Withdrawn)),
+                (Some(previous), Some(current)) => {
+                    let deprecated = previous.optionality
+                        != FieldOptionality::BackwardCompatibility
+                        && current.optionality == FieldOptionality::BackwardCompatibility;
+                    (
+                        FieldChanges::between(previous, current),
+                        deprecated.then_some(HistoryEvent::Deprecated),
+                    )
+                }
+            };
+            *previous = current;
+            Some(HistoryEntry {
+                version,
+                definition: current,
+                changes,
+                event,
+            })
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VERSIONS;
+
+    #[test]
+    fn can_parse_subjects() {
+        assert_eq!(
+            HistorySubject::parse("PID-13"),
+            FieldPath::parse("PID-13").map(HistorySubject::Path)
+        );
+        assert_eq!(
+            HistorySubject::parse("PID-5.1").map(|s| s.to_string()),
+            Some("PID-5.1".into())
+        );
+        assert_eq!(
+            HistorySubject::parse("CWE.2"),
+            Some(HistorySubject::Component {
+                datatype: "CWE",
+                component: 2
+            })
+        );
+        assert_eq!(HistorySubject::parse("PID-0"), None);
+        assert_eq!(HistorySubject::parse("PIDX-13"), None);
+        assert_eq!(HistorySubject::parse("CWE.2.1"), None);
+        assert_eq!(HistorySubject::parse("-13"), None);
+        assert_eq!(
+            HistorySubject::parse("CX.1"),
+            Some(HistorySubject::Component {
+                datatype: "CX",
+                component: 1
+            })
+        );
+        assert_eq!(
+            HistorySubject::parse("TS.1").map(|s| s.to_string()),
+            Some("TS.1".into())
+        );
+    }
+
+    #[test]
+    fn traces_fields_across_versions() {
+        let subject = HistorySubject::parse("PID-13").unwrap();
+        let history = field_history(subject).collect::<Vec<_>>();
+        assert!(history
+            .iter()
+            .map(|entry| entry.version)
+            .eq(VERSIONS.iter().copied()));
+        assert_eq!(history[0].event, Some(HistoryEvent::Introduced));
+        assert!(history[0].changes.is_empty());
+        for pair in history.windows(2) {
+            let (previous, current) = (pair[0].definition.unwrap(), pair[1].definition.unwrap());
+            assert_eq!(
+                pair[1].changes.datatype,
+                previous.datatype != current.datatype
+            );
+        }
+
+        let component = HistorySubject::parse("PID-5.1").unwrap();
+        let entry = field_history(component)
+            .find(|entry| entry.version == "2.5.1")
+            .expect("2.5.1 is compiled in");
+        assert_eq!(entry.definition.unwrap().description, "Family Name");
+
+        let missing = HistorySubject::parse("PID-999").unwrap();
+        assert!(
+            field_history(missing).all(|entry| entry.definition.is_none()
+                && entry.event.is_none()
+                && entry.changes.is_empty())
+        );
+    }
+
+    #[cfg(all(feature = "21", feature = "271"))]
+    #[test]
+    fn notes_deprecations() {
+        let subject = HistorySubject::parse("PID-2").unwrap();
+        let deprecated = field_history(subject)
+            .find(|entry| {
+                entry.definition.map(|d| d.optionality)
+                    == Some(FieldOptionality::BackwardCompatibility)
+            })
+            .expect("PID-2 is deprecated");
+        assert_eq!(deprecated.event, Some(HistoryEvent::Deprecated));
+        assert!(deprecated.changes.optionality);
+    }
+}
@@ -0,0 +1,104 @@
+use core::fmt::Display;
+
+use crate::{definitions, FieldPath, SubField};
+
+/// A segment field carrying a given item number, as found by [item_locations]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ItemLocation {
+    /// The version the field is defined in
+    pub version: &'static str,
+    /// The segment ID (`PID`)
+    pub segment: &'static str,
+    /// The 1-based field number within the segment
+    pub field: usize,
+    /// The field's definition
+    pub definition: &'static SubField,
+}
+
+impl ItemLocation {
+    /// The location of the field within its segment, such as `PID-5`
+    pub fn path(&self) -> FieldPath<'static> {
+        FieldPath {
+            segment: self.segment,
+            field: self.field,
+            component: None,
+            subcomponent: None,
+        }
+    }
+}
+
+impl Display for ItemLocation {
+    /// Formats the location as the version and path, such as `2.5.1 PID-5`
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} {}", self.version, self.path())
+    }
+}
+
+/// Find every segment field, in every compiled-in version, with the given item number
+///
+/// Versions are visited oldest first; within a version, segments are in no particular order.
+/// The same item number usually appears once per version, but some data elements are shared
+/// between segments. Item numbers are only known for the MSH, EVN, PID, PV1 and MRG segments,
+/// so items from any other segment aren't found.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let mut locations = item_locations(108);
+/// let patient_name = locations.find(|l| l.version == "2.5.1").expect("PID-5 is item 108");
+/// assert_eq!(patient_name.to_string(), "2.5.1 PID-5");
+/// assert_eq!(patient_name.definition.datatype, "XPN");
+/// ```
+pub fn item_locations(item: usize) -> impl Iterator<Item = ItemLocation> {
+    definitions().flat_map(move |(version, definition)| {
+        definition
+            .segments
+            .entries()
+            .flat_map(move |(segment, definition)| {
+                definition
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, field)| field.item_number == Some(item))
+                    .map(move |(i, field)| ItemLocation {
+                        version,
+                        segment,
+                        field: i + 1,
+                        definition: field,
+                    })
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_segment, VERSIONS};
+
+    #[test]
+    fn can_find_items_across_versions() {
+        let message_type = item_locations(9).collect::<Vec<_>>();
+        assert_eq!(message_type.len(), VERSIONS.len());
+        assert!(message_type
+            .iter()
+            .map(|location| location.version)
+            .eq(VERSIONS.iter().copied()));
+        assert!(message_type
+            .iter()
+            .all(|location| location.path() == FieldPath::parse("MSH-9").unwrap()));
+
+        assert_eq!(item_locations(0).count(), 0);
+    }
+
+    #[test]
+    fn fields_have_item_numbers() {
+        let pid = get_segment("2.5.1", "PID").expect("can get PID");
+        assert_eq!(pid.fields[4].item_number, Some(108));
+        assert_eq!(pid.fields[29].item_number, Some(741));
+        let pv1 = get_segment("2.5.1", "PV1").expect("can get PV1");
+        assert_eq!(pv1.fields[1].item_number, Some(132));
+        let msa = get_segment("2.5.1", "MSA").expect("can get MSA");
+        assert_eq!(msa.fields[0].item_number, None);
+    }
+}
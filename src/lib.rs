@@ -1,51 +1,52 @@
-use std::fmt::Display;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
-use phf::Map;
-
-include!(concat!(env!("OUT_DIR"), "/codegen.rs"));
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
-pub use codegen::VERSIONS;
+use core::fmt::Display;
 
-/// Get the description of the given table index
-///
-/// # Example
-///
-/// ```
-/// # use hl7_definitions::*;
-/// assert_eq!(table_description(3), Some("Event type"));
-/// ```
-#[inline]
-pub fn table_description(table: u16) -> Option<&'static str> {
-    codegen::TABLE_DESCRIPTIONS.get(&table).copied()
-}
+use phf::Map;
 
-/// Get a single value from a table
-///
-/// # Example
-///
-/// ```
-/// # use hl7_definitions::*;
-/// assert_eq!(table_value(3, "A01"), Some("ADT/ACK - Admit/visit notification"));
-/// ```
-#[inline]
-pub fn table_value(table: u16, key: &'static str) -> Option<&'static str> {
-    codegen::TABLES
-        .get(&table)
-        .and_then(|table| table.get(key).copied())
-}
+include!(concat!(env!("OUT_DIR"), "/codegen.rs"));
 
-/// Get _all_ the values for a given table
-///
-/// # Example
-///
-/// ```
-/// # use hl7_definitions::*;
-/// assert_eq!(table_values(7).unwrap().len(), 7);
-/// ```
-#[inline]
-pub fn table_values(table: u16) -> Option<&'static [(&'static str, &'static str)]> {
-    codegen::TABLES.get(&table).map(|table| table.entries)
-}
+#[cfg(feature = "table-enums")]
+pub mod codes;
+#[cfg(feature = "compressed")]
+mod compressed;
+#[cfg(feature = "alloc")]
+mod convert;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "fhir")]
+pub mod fhir;
+mod flatten;
+mod history;
+mod items;
+mod lookup;
+#[doc(hidden)]
+pub mod macros;
+mod path;
+mod phi;
+#[cfg(feature = "python")]
+mod python;
+mod tables;
+mod version;
+#[cfg(feature = "alloc")]
+mod visit;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "alloc")]
+pub use convert::*;
+pub use flatten::*;
+pub use history::*;
+pub use items::*;
+pub use lookup::*;
+pub use path::*;
+pub use phi::*;
+pub use tables::*;
+pub use version::*;
+#[cfg(feature = "alloc")]
+pub use visit::*;
 
 /// The root definition for a given version, describing the schema for that HL7 version
 #[derive(Debug)]
@@ -60,6 +61,8 @@ pub struct Definition {
 
 /// How "required" is the field
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum FieldOptionality {
     /// The field is optional
     Optional,
@@ -72,7 +75,7 @@ pub enum FieldOptionality {
 }
 
 impl Display for FieldOptionality {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             FieldOptionality::Optional => write!(f, "optional"),
             FieldOptionality::Required => write!(f, "required"),
@@ -84,6 +87,8 @@ impl Display for FieldOptionality {
 
 /// How many times a field can be repeated
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum FieldRepeatability {
     /// The field can be repeated to infinity
     Unbounded,
@@ -94,7 +99,7 @@ pub enum FieldRepeatability {
 }
 
 impl Display for FieldRepeatability {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             FieldRepeatability::Unbounded => write!(f, "unbounded"),
             FieldRepeatability::Single => write!(f, "singular"),
@@ -106,6 +111,7 @@ impl Display for FieldRepeatability {
 /// A field type (could be an HL7 field, component, or sub-component depending on its usage),
 /// effectively a datatype
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Field {
     /// The description of the field
     pub description: &'static str,
@@ -115,6 +121,7 @@ pub struct Field {
 
 /// Generally the lowest-level datatype, represents what a component or sub-component can be
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SubField {
     /// The datatype (field) of the sub-field
     pub datatype: &'static str,
@@ -122,16 +129,145 @@ pub struct SubField {
     pub description: &'static str,
     /// Whether the sub-field is required or not
     pub optionality: FieldOptionality,
-    /// The maximum length of the sub-field; if `None` then unbounded or not applicable
+    /// The maximum (item) length of the sub-field; if `None` then unbounded or not applicable
     pub max_length: Option<usize>,
+    /// The conformance length of the sub-field (`C.LEN` in HL7 2.7+), which takes precedence
+    /// over `max_length` when present; if `None` then not specified. The source data has no
+    /// conformance lengths, so only those listed in `assets/field_lengths.json` are known
+    /// (currently `MSH-10`, `CWE.1` and `CWE.2` in 2.7 and 2.7.1)
+    pub conformance_length: Option<usize>,
+    /// Whether the sub-field may be truncated to its conformance length; if `None` then not
+    /// specified
+    pub truncation: Option<Truncation>,
     /// How many times the sub-field can be repeated
     pub repeatability: FieldRepeatability,
     /// What table holds valid values for this sub-field
     pub table: Option<usize>,
+    /// The item number HL7 assigns to the data element, which identifies it across segments
+    /// and versions (conventionally written zero-padded, as in `00108`); if `None` then not
+    /// known. The source data has no item numbers, so they are only known for the fields of the
+    /// MSH, EVN, PID, PV1 and MRG segments listed in `assets/item_numbers.json`. See
+    /// [item_locations] to find every field with a given item number.
+    pub item_number: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    datatype_link: Link<Field>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    table_link: Link<Table>,
+}
+
+impl SubField {
+    /// The definition of the sub-field's datatype, from the same version as the sub-field
+    ///
+    /// The link is resolved when the definitions are generated, so following it doesn't need a
+    /// lookup. Returns `None` if the datatype isn't defined in the version, or if following it
+    /// would lead back to a datatype that contains this sub-field.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hl7_definitions::*;
+    /// let pid = get_segment("2.5.1", "PID").expect("can get PID");
+    /// let xpn = pid.fields[4].datatype_definition().expect("PID-5 is an XPN");
+    /// assert_eq!(xpn.subfields[0].description, "Family Name");
+    /// ```
+    pub const fn datatype_definition(&self) -> Option<&'static Field> {
+        self.datatype_link.0
+    }
+
+    /// The table holding valid values for the sub-field, if it has one and it is compiled into
+    /// the library
+    ///
+    /// Like [SubField::datatype_definition], the link is resolved ahead of time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hl7_definitions::*;
+    /// let pid = get_segment("2.5.1", "PID").expect("can get PID");
+    /// let sex = pid.fields[7].table_definition().expect("PID-8 has a table");
+    /// assert_eq!(sex.value("F"), Some("Female"));
+    /// ```
+    pub const fn table_definition(&self) -> Option<&'static Table> {
+        self.table_link.0
+    }
+
+    /// Whether values longer than the conformance length may be truncated to it
+    pub fn may_truncate(&self) -> bool {
+        self.conformance_length.is_some() && self.truncation == Some(Truncation::Allowed)
+    }
+
+    /// The length values may be truncated to, if truncation is allowed
+    pub fn truncation_length(&self) -> Option<usize> {
+        if self.may_truncate() {
+            self.conformance_length
+        } else {
+            None
+        }
+    }
+
+    /// Check the length of a value against the sub-field's conformance length, or its maximum
+    /// length if it doesn't have one
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hl7_definitions::*;
+    /// let control_id = get_segment("2.7", "MSH").expect("can get MSH").fields[9];
+    /// assert_eq!(control_id.check_length(50), LengthCheck::Valid);
+    /// assert_eq!(control_id.check_length(200), LengthCheck::TooLong(199));
+    /// ```
+    pub fn check_length(&self, length: usize) -> LengthCheck {
+        match self.conformance_length.or(self.max_length) {
+            Some(limit) if length > limit && self.may_truncate() => LengthCheck::Truncatable(limit),
+            Some(limit) if length > limit => LengthCheck::TooLong(limit),
+            _ => LengthCheck::Valid,
+        }
+    }
+}
+
+/// Whether a value may be truncated to its conformance length, written as a `#` (allowed) or
+/// `=` (not allowed) after the conformance length in HL7 2.7+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Truncation {
+    /// The value may be truncated (`#`)
+    Allowed,
+    /// The value must not be truncated (`=`)
+    NotAllowed,
+}
+
+impl Display for Truncation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Truncation::Allowed => write!(f, "#"),
+            Truncation::NotAllowed => write!(f, "="),
+        }
+    }
+}
+
+/// The outcome of checking the length of a value with [SubField::check_length]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LengthCheck {
+    /// The value is within the length limit
+    Valid,
+    /// The value is longer than the conformance length, but may be truncated to the given
+    /// length
+    Truncatable(usize),
+    /// The value is longer than the given length and may not be truncated
+    TooLong(usize),
+}
+
+impl LengthCheck {
+    /// Whether the value should be accepted
+    pub fn is_valid(&self) -> bool {
+        !matches!(self, LengthCheck::TooLong(_))
+    }
 }
 
 /// Schema for a segment (`MSH`, `PID`, etc)
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Segment {
     /// The description of the segment
     pub description: &'static str,
@@ -141,6 +277,7 @@ pub struct Segment {
 
 /// Schema for a mesasge (`ADT_A01`, etc)
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Message {
     /// A description of the message
     pub description: &'static str,
@@ -152,6 +289,7 @@ pub struct Message {
 
 /// A segment within a message
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MessageSegment {
     /// The name of the segment (3 capital letters)
     pub name: &'static str,
@@ -167,13 +305,142 @@ pub struct MessageSegment {
     /// While `children` defines a sequence of segments that could be included in the message,
     /// `compounds` defines a set of segments as choices to be allowed in te same position
     pub compounds: Option<&'static [MessageCompound]>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    segment_link: Link<Segment>,
 }
 
+impl MessageSegment {
+    /// The definition of the segment, from the same version as the message
+    ///
+    /// The link is resolved when the definitions are generated, so following it doesn't need a
+    /// lookup. For a choice, this is the definition of the first alternative. Returns `None`
+    /// for groups, and for segments which aren't defined in the version.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hl7_definitions::*;
+    /// let message = get_message("2.5.1", "ADT_A01").expect("can get ADT_A01");
+    /// let msh = message.segments[0].segment_definition().expect("MSH is defined");
+    /// assert_eq!(msh.description, "Message Header");
+    /// ```
+    pub const fn segment_definition(&self) -> Option<&'static Segment> {
+        self.segment_link.0
+    }
+
+    /// Whether this is a group of segments (such as `ORDER`) rather than a single segment
+    pub fn is_group(&self) -> bool {
+        self.children.is_some()
+    }
+
+    /// Whether this position is a choice between several segments, listed in `compounds`
+    pub fn is_choice(&self) -> bool {
+        self.compounds
+            .is_some_and(|compounds| !compounds.is_empty())
+    }
+
+    /// The segment IDs which may appear in this position: the alternatives for a choice, or
+    /// just the segment's own name otherwise. Groups have no alternatives of their own.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hl7_definitions::*;
+    /// let message = get_message("2.5.1", "OML_O21").expect("can get OML_O21");
+    /// let order = message.segments[1].children.expect("ORDER is a group");
+    /// let alternatives = order[1].alternatives().collect::<Vec<_>>();
+    /// assert_eq!(alternatives, ["OBR", "RQD"]);
+    /// ```
+    pub fn alternatives(&self) -> impl Iterator<Item = &'static str> {
+        let compounds = self.compounds.unwrap_or_default();
+        let own_name = (compounds.is_empty() && !self.is_group()).then_some(self.name);
+        own_name
+            .into_iter()
+            .chain(compounds.iter().filter_map(|compound| compound.name))
+    }
+
+    /// Whether a segment with the given ID satisfies this position: it is one of the
+    /// [alternatives](MessageSegment::alternatives), or for a group, it can start the group
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hl7_definitions::*;
+    /// let message = get_message("2.5.1", "OML_O21").expect("can get OML_O21");
+    /// let order = &message.segments[1];
+    /// assert!(order.accepts("ORC"));
+    /// assert!(!order.accepts("OBR"));
+    /// let choice = &order.children.expect("ORDER is a group")[1];
+    /// assert!(choice.accepts("RQD"));
+    /// ```
+    pub fn accepts(&self, segment: &str) -> bool {
+        match self.children {
+            Some(children) => {
+                // a group can start with any of its leading optional children, or its first
+                // required one
+                for child in children {
+                    if child.accepts(segment) {
+                        return true;
+                    }
+                    if child.min > 0 {
+                        return false;
+                    }
+                }
+                false
+            }
+            None => self
+                .alternatives()
+                .any(|alternative| alternative == segment),
+        }
+    }
+}
+
+/// A link from one definition to another, resolved when the definitions are generated
+///
+/// Links are compared by address rather than by following them, and aren't followed when
+/// debug-printing, so that comparing or printing a definition stays cheap.
+struct Link<T: 'static>(Option<&'static T>);
+
+impl<T> Clone for Link<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Link<T> {}
+
+impl<T> PartialEq for Link<T> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.0, other.0) {
+            (Some(a), Some(b)) => core::ptr::eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        }
+    }
+}
+
+impl<T> Eq for Link<T> {}
+
+impl<T> core::fmt::Debug for Link<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "Some(..)"),
+            None => write!(f, "None"),
+        }
+    }
+}
+
+/// One of the alternatives for a choice position in a message; see
+/// [MessageSegment::compounds]
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MessageCompound {
+    /// The segment ID of the alternative, if it is a concrete segment
     pub name: Option<&'static str>,
+    /// A description of the alternative
     pub description: &'static str,
+    /// Minimum number of times the alternative must appear when it is chosen
     pub min: usize,
+    /// The maximum number of times the alternative may appear when it is chosen
     pub max: usize,
 }
 
@@ -187,7 +454,25 @@ pub struct MessageCompound {
 /// ```
 #[inline]
 pub fn get_definition(version: &str) -> Option<&'static Definition> {
-    codegen::DEFINITIONS.get(version)
+    #[cfg(not(feature = "compressed"))]
+    return codegen::DEFINITIONS.get(version);
+    #[cfg(feature = "compressed")]
+    return compressed::definition(version);
+}
+
+/// Iterate over every version compiled into the library along with its definition, oldest
+/// first
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// assert_eq!(definitions().count(), VERSIONS.len());
+/// ```
+pub fn definitions() -> impl Iterator<Item = (&'static str, &'static Definition)> {
+    VERSIONS
+        .iter()
+        .filter_map(|version| get_definition(version).map(|definition| (*version, definition)))
 }
 
 /// Get a specific field for the given version
@@ -199,9 +484,7 @@ pub fn get_definition(version: &str) -> Option<&'static Definition> {
 /// assert!(get_field("2.5.1", "TS").is_some());
 /// ```
 pub fn get_field(version: &str, field: &str) -> Option<&'static Field> {
-    codegen::DEFINITIONS
-        .get(version)
-        .and_then(|defs| defs.fields.get(field))
+    get_definition(version).and_then(|defs| defs.fields.get(field))
 }
 
 /// Get a specific segment for the given version
@@ -213,9 +496,7 @@ pub fn get_field(version: &str, field: &str) -> Option<&'static Field> {
 /// assert!(get_segment("2.5.1", "MSH").is_some());
 /// ```
 pub fn get_segment(version: &str, segment: &str) -> Option<&'static Segment> {
-    codegen::DEFINITIONS
-        .get(version)
-        .and_then(|defs| defs.segments.get(segment))
+    get_definition(version).and_then(|defs| defs.segments.get(segment))
 }
 
 ///
@@ -226,9 +507,7 @@ pub fn get_segment(version: &str, segment: &str) -> Option<&'static Segment> {
 /// assert!(get_message("2.5.1", "ADT_A01").is_some());
 /// ```
 pub fn get_message(version: &str, message: &str) -> Option<&'static Message> {
-    codegen::DEFINITIONS
-        .get(version)
-        .and_then(|defs| defs.messages.get(message))
+    get_definition(version).and_then(|defs| defs.messages.get(message))
 }
 
 #[cfg(test)]
@@ -259,20 +538,16 @@ mod tests {
     fn can_get_table_values() {
         let values = table_values(91).expect("can get table 91 values");
         assert_eq!(values.len(), 2);
-        assert_eq!(
-            *values
-                .iter()
-                .find(|(k, _)| k == &"D")
-                .expect("can find entry D"),
-            ("D", "Deferred")
-        );
-        assert_eq!(
-            *values
-                .iter()
-                .find(|(k, _)| k == &"I")
-                .expect("can find entry I"),
-            ("I", "Immediate")
-        );
+        let (code, value) = values
+            .iter()
+            .find(|(k, _)| k == &"D")
+            .expect("can find entry D");
+        assert_eq!((*code, value.description), ("D", "Deferred"));
+        let (code, value) = values
+            .iter()
+            .find(|(k, _)| k == &"I")
+            .expect("can find entry I");
+        assert_eq!((*code, value.description), ("I", "Immediate"));
     }
 
     #[test]
@@ -297,9 +572,30 @@ mod tests {
         assert_eq!(ad.subfields[0].optionality, FieldOptionality::Optional);
         assert_eq!(ad.subfields[0].repeatability, FieldRepeatability::Single);
         assert_eq!(ad.subfields[0].max_length, Some(120));
+        assert_eq!(ad.subfields[0].conformance_length, None);
         assert_eq!(ad.subfields[0].table, None);
     }
 
+    #[cfg(feature = "27")]
+    #[test]
+    fn can_check_field_lengths() {
+        let control_id = get_segment("2.7", "MSH").expect("can get MSH").fields[9];
+        assert_eq!(control_id.conformance_length, Some(199));
+        assert_eq!(control_id.truncation, Some(Truncation::NotAllowed));
+        assert!(!control_id.may_truncate());
+        assert_eq!(control_id.check_length(199), LengthCheck::Valid);
+        assert_eq!(control_id.check_length(200), LengthCheck::TooLong(199));
+
+        let text = get_field("2.7", "CWE").expect("can get CWE").subfields[1];
+        assert_eq!(text.truncation_length(), Some(199));
+        assert_eq!(text.check_length(250), LengthCheck::Truncatable(199));
+        assert!(text.check_length(250).is_valid());
+
+        let control_id = get_segment("2.5.1", "MSH").expect("can get MSH").fields[9];
+        assert_eq!(control_id.truncation_length(), None);
+        assert_eq!(control_id.check_length(21), LengthCheck::TooLong(20));
+    }
+
     #[test]
     fn can_get_segments_for_version() {
         let msh = get_segment("2.5.1", "MSH").expect("can get MSH segment for v2.5.1");
@@ -317,4 +613,51 @@ mod tests {
         assert_eq!(msh.min, 1);
         assert_eq!(msh.max, 1);
     }
+
+    #[test]
+    fn can_interpret_choices() {
+        let o21 = get_message("2.5.1", "OML_O21").expect("can get OML_O21 message for v2.5.1");
+        let msh = o21.segments[0];
+        assert!(!msh.is_choice());
+        assert_eq!(msh.alternatives().collect::<Vec<_>>(), ["MSH"]);
+
+        let order = o21.segments[1];
+        assert!(order.is_group());
+        assert_eq!(order.alternatives().count(), 0);
+        assert!(order.accepts("ORC"));
+
+        let choice = order.children.expect("ORDER has children")[1];
+        assert!(choice.is_choice());
+        assert!(choice.accepts("OBR"));
+        assert!(choice.accepts("RQD"));
+        assert!(!choice.accepts("NTE"));
+    }
+
+    #[test]
+    fn can_follow_links() {
+        let pid = get_segment("2.5.1", "PID").expect("can get PID segment for v2.5.1");
+        let xpn = pid.fields[4]
+            .datatype_definition()
+            .expect("PID-5 links to its datatype");
+        assert_eq!(xpn, get_field("2.5.1", "XPN").expect("can get XPN"));
+        let fn_ = xpn.subfields[0]
+            .datatype_definition()
+            .expect("XPN.1 links to its datatype");
+        assert_eq!(fn_.description, "Family Name");
+
+        let sex = pid.fields[7]
+            .table_definition()
+            .expect("PID-8 links to its table");
+        assert_eq!(sex.id(), TableId(1));
+        assert!(pid.fields[0].table_definition().is_none());
+
+        let a01 = get_message("2.5.1", "ADT_A01").expect("can get ADT_A01 message for v2.5.1");
+        let evn = a01.segments[2]
+            .segment_definition()
+            .expect("EVN links to its definition");
+        assert_eq!(evn, get_segment("2.5.1", "EVN").expect("can get EVN"));
+
+        let o21 = get_message("2.5.1", "OML_O21").expect("can get OML_O21 message for v2.5.1");
+        assert!(o21.segments[1].segment_definition().is_none());
+    }
 }
@@ -0,0 +1,668 @@
+use core::fmt::Display;
+
+use crate::codegen::{DISABLED_DATATYPES, DISABLED_MESSAGES, DISABLED_SEGMENTS};
+use crate::tables::table_map;
+use crate::{
+    get_definition, get_table, Definition, Field, Message, Segment, Table, TableId, Version,
+    ALL_VERSIONS, VERSIONS,
+};
+
+/// Why a lookup with one of the `try_get_*` functions failed
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LookupError<'a> {
+    /// The version isn't one the library knows about
+    UnknownVersion {
+        /// The version that was requested
+        version: &'a str,
+        /// A known version that looks like the one requested, such as `2.5.1` for `2.5.1-US`
+        did_you_mean: Option<&'static str>,
+    },
+    /// The version is known, but its cargo feature isn't enabled so it isn't compiled in
+    VersionDisabled {
+        /// The version that was requested
+        version: &'static str,
+    },
+    /// The datatype doesn't exist in the version
+    UnknownDatatype {
+        /// The version that was searched
+        version: &'a str,
+        /// The datatype that was requested
+        id: &'a str,
+        /// A datatype in the version with a similar ID
+        did_you_mean: Option<&'static str>,
+        /// The compiled-in versions that do define the datatype
+        found_in: VersionSet,
+    },
+    /// The segment doesn't exist in the version
+    UnknownSegment {
+        /// The version that was searched
+        version: &'a str,
+        /// The segment that was requested
+        id: &'a str,
+        /// A segment in the version with a similar ID
+        did_you_mean: Option<&'static str>,
+        /// The compiled-in versions that do define the segment
+        found_in: VersionSet,
+    },
+    /// The message doesn't exist in the version
+    UnknownMessage {
+        /// The version that was searched
+        version: &'a str,
+        /// The message that was requested
+        id: &'a str,
+        /// A message in the version with a similar ID
+        did_you_mean: Option<&'static str>,
+        /// The compiled-in versions that do define the message
+        found_in: VersionSet,
+    },
+    /// The datatype exists in the version, but was left out by the message family features
+    /// because none of the compiled-in messages use it
+    DatatypeDisabled {
+        /// The version that was searched
+        version: &'a str,
+        /// The datatype that was requested
+        id: &'a str,
+        /// A cargo feature whose messages use the datatype, such as `adt`
+        feature: &'static str,
+    },
+    /// The segment exists in the version, but was left out by the message family features
+    /// because none of the compiled-in messages use it
+    SegmentDisabled {
+        /// The version that was searched
+        version: &'a str,
+        /// The segment that was requested
+        id: &'a str,
+        /// A cargo feature whose messages use the segment, such as `orm`
+        feature: &'static str,
+    },
+    /// The message exists in the version, but was left out by the message family features
+    MessageDisabled {
+        /// The version that was searched
+        version: &'a str,
+        /// The message that was requested
+        id: &'a str,
+        /// The cargo feature that includes the message, such as `adt`
+        feature: &'static str,
+    },
+    /// The table doesn't exist
+    UnknownTable {
+        /// The table that was requested
+        table: TableId,
+    },
+    /// The `tables` feature isn't enabled, so no tables are compiled in
+    TablesDisabled,
+}
+
+impl Display for LookupError<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let (kind, version, id, did_you_mean, found_in) = match self {
+            LookupError::UnknownVersion {
+                version,
+                did_you_mean,
+            } => {
+                write!(f, "unknown HL7 version `{version}`")?;
+                if let Some(version) = did_you_mean {
+                    write!(f, "; did you mean `{version}`?")?;
+                }
+                return Ok(());
+            }
+            LookupError::VersionDisabled { version } => {
+                write!(f, "HL7 version {version} is not compiled in; enable the `")?;
+                for c in version.chars().filter(|c| *c != '.') {
+                    write!(f, "{c}")?;
+                }
+                return write!(f, "` feature");
+            }
+            LookupError::DatatypeDisabled {
+                version,
+                id,
+                feature,
+            }
+            | LookupError::SegmentDisabled {
+                version,
+                id,
+                feature,
+            }
+            | LookupError::MessageDisabled {
+                version,
+                id,
+                feature,
+            } => {
+                let kind = match self {
+                    LookupError::DatatypeDisabled { .. } => "datatype",
+                    LookupError::SegmentDisabled { .. } => "segment",
+                    _ => "message",
+                };
+                return write!(
+                    f,
+                    "{kind} `{id}` in HL7 {version} is not compiled in; enable the `{feature}` feature"
+                );
+            }
+            LookupError::UnknownTable { table } => return write!(f, "unknown HL7 table {table}"),
+            LookupError::TablesDisabled => {
+                return write!(
+                    f,
+                    "HL7 tables are not compiled in; enable the `tables` feature"
+                )
+            }
+            LookupError::UnknownDatatype {
+                version,
+                id,
+                did_you_mean,
+                found_in,
+            } => ("datatype", version, id, did_you_mean, found_in),
+            LookupError::UnknownSegment {
+                version,
+                id,
+                did_you_mean,
+                found_in,
+            } => ("segment", version, id, did_you_mean, found_in),
+            LookupError::UnknownMessage {
+                version,
+                id,
+                did_you_mean,
+                found_in,
+            } => ("message", version, id, did_you_mean, found_in),
+        };
+
+        write!(f, "unknown {kind} `{id}` in HL7 {version}")?;
+        if let Some(id) = did_you_mean {
+            write!(f, "; did you mean `{id}`?")?;
+        }
+        if !found_in.is_empty() {
+            write!(f, "; it exists in versions {found_in}")?;
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for LookupError<'_> {}
+
+/// A set of the versions in [ALL_VERSIONS]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct VersionSet(u64);
+
+impl VersionSet {
+    /// Whether the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// The number of versions in the set
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Whether the set contains the given version
+    pub fn contains(&self, version: &str) -> bool {
+        self.iter().any(|v| v == version)
+    }
+
+    /// The versions in the set, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &'static str> + '_ {
+        ALL_VERSIONS
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.0 & (1 << i) != 0)
+            .map(|(_, version)| *version)
+    }
+
+    fn insert(&mut self, version: &str) {
+        if let Some(i) = ALL_VERSIONS.iter().position(|v| *v == version) {
+            self.0 |= 1 << i;
+        }
+    }
+}
+
+impl Display for VersionSet {
+    /// Formats the versions as a comma-separated list, such as `2.5, 2.5.1`
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (i, version) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{version}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A list of IDs to make suggestions from, either the keys of a map or a plain list
+#[derive(Copy, Clone)]
+pub(crate) enum Keys {
+    Fields(&'static [(&'static str, Field)]),
+    Segments(&'static [(&'static str, Segment)]),
+    Messages(&'static [(&'static str, Message)]),
+    List(&'static [&'static str]),
+}
+
+impl Keys {
+    const fn len(self) -> usize {
+        match self {
+            Keys::Fields(entries) => entries.len(),
+            Keys::Segments(entries) => entries.len(),
+            Keys::Messages(entries) => entries.len(),
+            Keys::List(list) => list.len(),
+        }
+    }
+
+    const fn get(self, i: usize) -> &'static str {
+        match self {
+            Keys::Fields(entries) => entries[i].0,
+            Keys::Segments(entries) => entries[i].0,
+            Keys::Messages(entries) => entries[i].0,
+            Keys::List(list) => list[i],
+        }
+    }
+}
+
+/// The edit distance between two IDs, ignoring ASCII case; IDs longer than 32 bytes are never
+/// considered similar
+///
+/// This and [suggest] are `const fn`s so that the lookup macros can make the same suggestions
+/// while compiling.
+const fn distance(a: &str, b: &str) -> Option<usize> {
+    const MAX: usize = 32;
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() > MAX || b.len() > MAX {
+        return None;
+    }
+    let mut previous = [0; MAX + 1];
+    let mut current = [0; MAX + 1];
+    let mut j = 0;
+    while j <= b.len() {
+        previous[j] = j;
+        j += 1;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        current[0] = i + 1;
+        let mut j = 0;
+        while j < b.len() {
+            let substitution = previous[j] + !a[i].eq_ignore_ascii_case(&b[j]) as usize;
+            let deletion = previous[j + 1] + 1;
+            let insertion = current[j] + 1;
+            let mut best = substitution;
+            if deletion < best {
+                best = deletion;
+            }
+            if insertion < best {
+                best = insertion;
+            }
+            current[j + 1] = best;
+            j += 1;
+        }
+        let swap = previous;
+        previous = current;
+        current = swap;
+        i += 1;
+    }
+    Some(previous[b.len()])
+}
+
+/// Whether `a` sorts before `b`, so that suggestions don't depend on hash order
+const fn less(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut i = 0;
+    while i < a.len() && i < b.len() {
+        if a[i] != b[i] {
+            return a[i] < b[i];
+        }
+        i += 1;
+    }
+    a.len() < b.len()
+}
+
+/// The candidate most similar to `id`, if any are close enough to be a plausible typo
+pub(crate) const fn suggest(id: &str, candidates: Keys) -> Option<&'static str> {
+    let threshold = if id.len() / 3 > 1 { id.len() / 3 } else { 1 };
+    let mut best: Option<(usize, &'static str)> = None;
+    let mut i = 0;
+    while i < candidates.len() {
+        let candidate = candidates.get(i);
+        if let Some(d) = distance(id, candidate) {
+            let better = match best {
+                None => d <= threshold,
+                Some((best_d, best_candidate)) => {
+                    d < best_d || (d == best_d && less(candidate, best_candidate))
+                }
+            };
+            if better {
+                best = Some((d, candidate));
+            }
+        }
+        i += 1;
+    }
+    match best {
+        Some((_, candidate)) => Some(candidate),
+        None => None,
+    }
+}
+
+/// The versions whose definitions contain the given ID
+fn found_in(contains: impl Fn(&'static Definition) -> bool) -> VersionSet {
+    let mut versions = VersionSet::default();
+    for version in VERSIONS {
+        if get_definition(version).is_some_and(&contains) {
+            versions.insert(version);
+        }
+    }
+    versions
+}
+
+/// The feature that includes an item the message family features left out of the version, if
+/// they did
+fn disabled_feature(
+    disabled: &phf::Map<&'static str, (&'static str, u64)>,
+    version: &str,
+    id: &str,
+) -> Option<&'static str> {
+    let (feature, versions) = disabled.get(id)?;
+    let i = ALL_VERSIONS.iter().position(|v| *v == version)?;
+    (versions & (1 << i) != 0).then_some(*feature)
+}
+
+/// Like [get_definition](crate::get_definition), but explains why the version isn't available
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// assert!(try_get_definition("2.5.1").is_ok());
+/// let error = try_get_definition("2.5.1-US").expect_err("2.5.1-US isn't a version");
+/// assert_eq!(error.to_string(), "unknown HL7 version `2.5.1-US`; did you mean `2.5.1`?");
+/// ```
+pub fn try_get_definition(version: &str) -> Result<&'static Definition, LookupError<'_>> {
+    if let Some(definition) = get_definition(version) {
+        return Ok(definition);
+    }
+    if let Some(version) = ALL_VERSIONS.iter().find(|v| **v == version) {
+        return Err(LookupError::VersionDisabled { version });
+    }
+
+    let requested = Version::parse(version);
+    let did_you_mean = ALL_VERSIONS
+        .iter()
+        .find(|v| requested.is_some() && Version::parse(v) == requested)
+        .copied()
+        .or_else(|| suggest(version, Keys::List(ALL_VERSIONS)));
+    Err(LookupError::UnknownVersion {
+        version,
+        did_you_mean,
+    })
+}
+
+/// Like [get_field](crate::get_field), but explains why the datatype wasn't found, including
+/// when it was left out by the message family features
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let error = try_get_field("2.5.1", "XPM").expect_err("XPM doesn't exist");
+/// assert!(matches!(
+///     error,
+///     LookupError::UnknownDatatype { did_you_mean: Some("XPN"), .. }
+/// ));
+/// ```
+pub fn try_get_field<'a>(
+    version: &'a str,
+    field: &'a str,
+) -> Result<&'static Field, LookupError<'a>> {
+    let definition = try_get_definition(version)?;
+    if let Some(feature) = disabled_feature(&DISABLED_DATATYPES, version, field) {
+        return Err(LookupError::DatatypeDisabled {
+            version,
+            id: field,
+            feature,
+        });
+    }
+    definition
+        .fields
+        .get(field)
+        .ok_or_else(|| LookupError::UnknownDatatype {
+            version,
+            id: field,
+            did_you_mean: suggest(field, Keys::Fields(definition.fields.entries)),
+            found_in: found_in(|definition| definition.fields.contains_key(field)),
+        })
+}
+
+/// Like [get_segment](crate::get_segment), but explains why the segment wasn't found, including
+/// when it was left out by the message family features
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let error = try_get_segment("2.5.1", "pid").expect_err("segment IDs are upper case");
+/// assert_eq!(
+///     error.to_string(),
+///     "unknown segment `pid` in HL7 2.5.1; did you mean `PID`?"
+/// );
+/// ```
+pub fn try_get_segment<'a>(
+    version: &'a str,
+    segment: &'a str,
+) -> Result<&'static Segment, LookupError<'a>> {
+    let definition = try_get_definition(version)?;
+    if let Some(feature) = disabled_feature(&DISABLED_SEGMENTS, version, segment) {
+        return Err(LookupError::SegmentDisabled {
+            version,
+            id: segment,
+            feature,
+        });
+    }
+    definition
+        .segments
+        .get(segment)
+        .ok_or_else(|| LookupError::UnknownSegment {
+            version,
+            id: segment,
+            did_you_mean: suggest(segment, Keys::Segments(definition.segments.entries)),
+            found_in: found_in(|definition| definition.segments.contains_key(segment)),
+        })
+}
+
+/// Like [get_message](crate::get_message), but explains why the message wasn't found,
+/// including when it was left out by the message family features
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let error = try_get_message("2.5.1", "ADT_A1").expect_err("the event is A01");
+/// assert!(matches!(
+///     error,
+///     LookupError::UnknownMessage { did_you_mean: Some("ADT_A01"), .. }
+/// ));
+/// ```
+pub fn try_get_message<'a>(
+    version: &'a str,
+    message: &'a str,
+) -> Result<&'static Message, LookupError<'a>> {
+    let definition = try_get_definition(version)?;
+    if let Some(message) = definition.messages.get(message) {
+        return Ok(message);
+    }
+
+    if let Some(feature) = disabled_feature(&DISABLED_MESSAGES, version, message) {
+        return Err(LookupError::MessageDisabled {
+            version,
+            id: message,
+            feature,
+        });
+    }
+    Err(LookupError::UnknownMessage {
+        version,
+        id: message,
+        did_you_mean: suggest(message, Keys::Messages(definition.messages.entries)),
+        found_in: found_in(|definition| definition.messages.contains_key(message)),
+    })
+}
+
+/// Like [get_table](crate::get_table), but explains why the table wasn't found
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// assert!(try_get_table(1).is_ok());
+/// assert_eq!(
+///     try_get_table(9999).err(),
+///     Some(LookupError::UnknownTable { table: TableId(9999) })
+/// );
+/// ```
+pub fn try_get_table<T: Into<TableId>>(table: T) -> Result<Table, LookupError<'static>> {
+    if table_map().is_empty() {
+        return Err(LookupError::TablesDisabled);
+    }
+    let table = table.into();
+    get_table(table).ok_or(LookupError::UnknownTable { table })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explains_unknown_versions() {
+        assert_eq!(
+            try_get_definition("2.5.1 ").err(),
+            Some(LookupError::UnknownVersion {
+                version: "2.5.1 ",
+                did_you_mean: Some("2.5.1")
+            })
+        );
+        assert_eq!(
+            try_get_definition("2.55").err(),
+            Some(LookupError::UnknownVersion {
+                version: "2.55",
+                did_you_mean: Some("2.5")
+            })
+        );
+        assert_eq!(
+            try_get_definition("garbage").err(),
+            Some(LookupError::UnknownVersion {
+                version: "garbage",
+                did_you_mean: None
+            })
+        );
+        assert_eq!(
+            LookupError::VersionDisabled { version: "2.8.2" }.to_string(),
+            "HL7 version 2.8.2 is not compiled in; enable the `282` feature"
+        );
+    }
+
+    #[test]
+    fn explains_unknown_items() {
+        let error = try_get_segment("2.5.1", "PIX").expect_err("PIX isn't a segment");
+        let LookupError::UnknownSegment {
+            did_you_mean,
+            found_in,
+            ..
+        } = error
+        else {
+            panic!("PIX isn't a segment");
+        };
+        assert_eq!(did_you_mean, Some("PID"));
+        assert!(found_in.is_empty());
+        assert_eq!(
+            error.to_string(),
+            "unknown segment `PIX` in HL7 2.5.1; did you mean `PID`?"
+        );
+
+        let pid = super::found_in(|definition| definition.segments.contains_key("PID"));
+        assert_eq!(pid.len(), VERSIONS.len());
+        assert!(pid.iter().eq(VERSIONS.iter().copied()));
+
+        assert_eq!(
+            try_get_message("2.5.1", "ZZZ_Z99").err(),
+            Some(LookupError::UnknownMessage {
+                version: "2.5.1",
+                id: "ZZZ_Z99",
+                did_you_mean: None,
+                found_in: VersionSet::default(),
+            })
+        );
+    }
+
+    #[test]
+    fn explains_disabled_items() {
+        // there is only anything to check here when some message families are turned off
+        let disabled = |map: &'static phf::Map<&'static str, (&'static str, u64)>| {
+            map.entries().flat_map(|(id, (feature, versions))| {
+                ALL_VERSIONS
+                    .iter()
+                    .enumerate()
+                    .filter(move |(i, version)| {
+                        versions & (1 << i) != 0 && VERSIONS.contains(version)
+                    })
+                    .map(move |(_, version)| (*version, *id, *feature))
+            })
+        };
+        for (version, id, feature) in disabled(&DISABLED_DATATYPES) {
+            assert_eq!(
+                try_get_field(version, id).err(),
+                Some(LookupError::DatatypeDisabled {
+                    version,
+                    id,
+                    feature
+                })
+            );
+        }
+        for (version, id, feature) in disabled(&DISABLED_SEGMENTS) {
+            assert_eq!(
+                try_get_segment(version, id).err(),
+                Some(LookupError::SegmentDisabled {
+                    version,
+                    id,
+                    feature
+                })
+            );
+        }
+        for (version, id, feature) in disabled(&DISABLED_MESSAGES) {
+            assert_eq!(
+                try_get_message(version, id).err(),
+                Some(LookupError::MessageDisabled {
+                    version,
+                    id,
+                    feature
+                })
+            );
+        }
+        assert_eq!(
+            LookupError::MessageDisabled {
+                version: "2.5.1",
+                id: "ADT_A01",
+                feature: "adt"
+            }
+            .to_string(),
+            "message `ADT_A01` in HL7 2.5.1 is not compiled in; enable the `adt` feature"
+        );
+        assert_eq!(
+            LookupError::SegmentDisabled {
+                version: "2.5.1",
+                id: "ORC",
+                feature: "orm"
+            }
+            .to_string(),
+            "segment `ORC` in HL7 2.5.1 is not compiled in; enable the `orm` feature"
+        );
+    }
+
+    #[test]
+    fn formats_version_sets() {
+        let mut versions = VersionSet::default();
+        assert!(versions.is_empty());
+        versions.insert("2.5.1");
+        versions.insert("2.3");
+        versions.insert("9.9");
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions.to_string(), "2.3, 2.5.1");
+        assert!(
+            ALL_VERSIONS.len() <= 64,
+            "VersionSet can hold every version"
+        );
+    }
+}
@@ -0,0 +1,577 @@
+//! Support for the [hl7_field](crate::hl7_field), [hl7_segment](crate::hl7_segment),
+//! [hl7_message](crate::hl7_message) and [hl7_path](crate::hl7_path) macros. Nothing in here is
+//! meant to be used directly.
+//!
+//! Without the `compressed` feature the definitions are static data, so lookups are `const fn`s
+//! which the macros evaluate while compiling; a lookup that fails panics, which the compiler
+//! reports as an error. With the `compressed` feature the definitions only exist once they have
+//! been decoded at runtime, so the same lookups are made (and panic) at runtime instead.
+
+/// Evaluate a lookup while compiling when the definitions are available to do so
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "compressed"))]
+macro_rules! __hl7_resolve {
+    ($ty:ty, $lookup:expr) => {{
+        const RESOLVED: $ty = $lookup;
+        RESOLVED
+    }};
+}
+
+/// Evaluate a lookup while compiling when the definitions are available to do so
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "compressed")]
+macro_rules! __hl7_resolve {
+    ($ty:ty, $lookup:expr) => {{
+        let resolved: $ty = $lookup;
+        resolved
+    }};
+}
+
+/// Get a datatype definition, checking that it exists when compiling
+///
+/// This is [get_field](crate::get_field) for versions and IDs that are known ahead of time:
+/// instead of returning an `Option` to unwrap, a version or datatype that doesn't exist (or
+/// whose feature isn't enabled) is a compile error, with a suggestion where there is a similar
+/// ID. Both arguments must be constant strings.
+///
+/// With the `compressed` feature the definitions aren't available when compiling, so the
+/// macro panics at runtime instead.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let xpn: &'static Field = hl7_field!("2.5.1", "XPN");
+/// assert_eq!(xpn.description, "Extended Person Name");
+/// ```
+#[macro_export]
+macro_rules! hl7_field {
+    ($version:expr, $field:expr $(,)?) => {
+        $crate::__hl7_resolve!(
+            &'static $crate::Field,
+            $crate::macros::field($version, $field)
+        )
+    };
+}
+
+/// Get a segment definition, checking that it exists when compiling
+///
+/// This is [get_segment](crate::get_segment) for versions and IDs that are known ahead of
+/// time; see [hl7_field](crate::hl7_field) for how errors are reported.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let pid: &'static Segment = hl7_segment!("2.5.1", "PID");
+/// assert_eq!(pid.description, "Patient Identification");
+/// ```
+///
+/// A typo is caught when compiling, with the error suggesting `PID`:
+///
+#[cfg_attr(not(feature = "compressed"), doc = "```compile_fail")]
+#[cfg_attr(feature = "compressed", doc = "```should_panic")]
+/// # use hl7_definitions::*;
+/// let pid = hl7_segment!("2.5.1", "PIX");
+/// ```
+#[macro_export]
+macro_rules! hl7_segment {
+    ($version:expr, $segment:expr $(,)?) => {
+        $crate::__hl7_resolve!(
+            &'static $crate::Segment,
+            $crate::macros::segment($version, $segment)
+        )
+    };
+}
+
+/// Get a message definition, checking that it exists when compiling
+///
+/// This is [get_message](crate::get_message) for versions and IDs that are known ahead of
+/// time; see [hl7_field](crate::hl7_field) for how errors are reported.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let a01: &'static Message = hl7_message!("2.5.1", "ADT_A01");
+/// assert_eq!(a01.segments[0].name, "MSH");
+/// ```
+///
+/// Versions that aren't compiled in are caught too:
+///
+#[cfg_attr(not(feature = "compressed"), doc = "```compile_fail")]
+#[cfg_attr(feature = "compressed", doc = "```should_panic")]
+/// # use hl7_definitions::*;
+/// let a01 = hl7_message!("2.5.2", "ADT_A01");
+/// ```
+#[macro_export]
+macro_rules! hl7_message {
+    ($version:expr, $message:expr $(,)?) => {
+        $crate::__hl7_resolve!(
+            &'static $crate::Message,
+            $crate::macros::message($version, $message)
+        )
+    };
+}
+
+/// Resolve a path such as `PID-5.1`, checking that it exists when compiling
+///
+/// This is [resolve_path](crate::resolve_path) for versions and paths that are known ahead of
+/// time; see [hl7_field](crate::hl7_field) for how errors are reported.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let family_name: ResolvedPath = hl7_path!("2.5.1", "PID-5.1");
+/// assert_eq!(family_name.target().description, "Family Name");
+/// ```
+///
+/// Positions past the end of a segment or datatype are caught when compiling:
+///
+#[cfg_attr(not(feature = "compressed"), doc = "```compile_fail")]
+#[cfg_attr(feature = "compressed", doc = "```should_panic")]
+/// # use hl7_definitions::*;
+/// let path = hl7_path!("2.5.1", "PID-5.99");
+/// ```
+#[macro_export]
+macro_rules! hl7_path {
+    ($version:expr, $path:expr $(,)?) => {
+        $crate::__hl7_resolve!($crate::ResolvedPath, $crate::macros::path($version, $path))
+    };
+}
+
+#[cfg(not(feature = "compressed"))]
+pub use lookup::*;
+
+#[cfg(not(feature = "compressed"))]
+mod lookup {
+    use phf::Map;
+
+    use crate::lookup::{suggest, Keys};
+    use crate::{
+        codegen, Definition, Field, Message, ResolvedPath, Segment, SubField, ALL_VERSIONS,
+        VERSIONS,
+    };
+
+    /// An error message, built up without allocating so that it can be formatted while
+    /// compiling
+    struct Error {
+        buf: [u8; 512],
+        len: usize,
+    }
+
+    impl Error {
+        const fn new() -> Error {
+            Error {
+                buf: [0; 512],
+                len: 0,
+            }
+        }
+
+        const fn push(mut self, s: &str) -> Error {
+            let bytes = s.as_bytes();
+            let mut i = 0;
+            while i < bytes.len() && self.len < self.buf.len() {
+                self.buf[self.len] = bytes[i];
+                self.len += 1;
+                i += 1;
+            }
+            self
+        }
+
+        const fn push_number(mut self, n: usize) -> Error {
+            let mut digits = [0; 20];
+            let (mut n, mut count) = (n, 0);
+            loop {
+                digits[count] = b'0' + (n % 10) as u8;
+                count += 1;
+                n /= 10;
+                if n == 0 {
+                    break;
+                }
+            }
+            while count > 0 && self.len < self.buf.len() {
+                count -= 1;
+                self.buf[self.len] = digits[count];
+                self.len += 1;
+            }
+            self
+        }
+
+        /// Push the name of a version's feature, which is the version without its dots
+        const fn push_feature(mut self, version: &str) -> Error {
+            let bytes = version.as_bytes();
+            let mut i = 0;
+            while i < bytes.len() && self.len < self.buf.len() {
+                if bytes[i] != b'.' {
+                    self.buf[self.len] = bytes[i];
+                    self.len += 1;
+                }
+                i += 1;
+            }
+            self
+        }
+
+        const fn fail(self) -> ! {
+            let (message, _) = self.buf.split_at(self.len);
+            match core::str::from_utf8(message) {
+                Ok(message) => panic!("{}", message),
+                Err(_) => panic!("HL7 definition lookup failed"),
+            }
+        }
+    }
+
+    /// The kinds of item that can be looked up in a version
+    #[derive(Copy, Clone)]
+    enum Kind {
+        Datatype,
+        Segment,
+        Message,
+    }
+
+    impl Kind {
+        const fn name(self) -> &'static str {
+            match self {
+                Kind::Datatype => "datatype",
+                Kind::Segment => "segment",
+                Kind::Message => "message",
+            }
+        }
+
+        /// The items of this kind left out by the message family features
+        const fn disabled(self) -> &'static Map<&'static str, (&'static str, u64)> {
+            match self {
+                Kind::Datatype => &codegen::DISABLED_DATATYPES,
+                Kind::Segment => &codegen::DISABLED_SEGMENTS,
+                Kind::Message => &codegen::DISABLED_MESSAGES,
+            }
+        }
+
+        const fn contains(self, definition: &'static Definition, id: &str) -> bool {
+            match self {
+                Kind::Datatype => find(definition.fields, id).is_some(),
+                Kind::Segment => find(definition.segments, id).is_some(),
+                Kind::Message => find(definition.messages, id).is_some(),
+            }
+        }
+
+        const fn suggest(self, definition: &'static Definition, id: &str) -> Option<&'static str> {
+            match self {
+                Kind::Datatype => suggest(id, Keys::Fields(definition.fields.entries)),
+                Kind::Segment => suggest(id, Keys::Segments(definition.segments.entries)),
+                Kind::Message => suggest(id, Keys::Messages(definition.messages.entries)),
+            }
+        }
+    }
+
+    const fn eq(a: &str, b: &str) -> bool {
+        let (a, b) = (a.as_bytes(), b.as_bytes());
+        if a.len() != b.len() {
+            return false;
+        }
+        let mut i = 0;
+        while i < a.len() {
+            if a[i] != b[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    const fn find<V>(map: &'static Map<&'static str, V>, key: &str) -> Option<&'static V> {
+        let mut i = 0;
+        while i < map.entries.len() {
+            if eq(map.entries[i].0, key) {
+                return Some(&map.entries[i].1);
+            }
+            i += 1;
+        }
+        None
+    }
+
+    const fn with_suggestion(error: Error, suggestion: Option<&str>) -> Error {
+        match suggestion {
+            Some(suggestion) => error.push("; did you mean `").push(suggestion).push("`?"),
+            None => error,
+        }
+    }
+
+    pub const fn definition(version: &str) -> &'static Definition {
+        if let Some(definition) = find(&codegen::DEFINITIONS, version) {
+            return definition;
+        }
+        let error = Error::new().push("HL7 version `").push(version).push("` ");
+
+        let mut i = 0;
+        while i < ALL_VERSIONS.len() {
+            if eq(ALL_VERSIONS[i], version) {
+                error
+                    .push("is not compiled in; enable the `")
+                    .push_feature(version)
+                    .push("` feature")
+                    .fail();
+            }
+            i += 1;
+        }
+
+        let error = error.push("is unknown");
+        with_suggestion(error, suggest(version, Keys::List(ALL_VERSIONS))).fail()
+    }
+
+    const fn unknown(kind: Kind, version: &str, definition: &'static Definition, id: &str) -> ! {
+        check_disabled(kind, version, id);
+        let error = Error::new()
+            .push("unknown ")
+            .push(kind.name())
+            .push(" `")
+            .push(id)
+            .push("` in HL7 ")
+            .push(version);
+        let mut error = with_suggestion(error, kind.suggest(definition, id));
+
+        let mut found = 0;
+        let mut i = 0;
+        while i < VERSIONS.len() {
+            if let Some(other) = find(&codegen::DEFINITIONS, VERSIONS[i]) {
+                if kind.contains(other, id) {
+                    error = error
+                        .push(if found == 0 {
+                            "; it exists in versions "
+                        } else {
+                            ", "
+                        })
+                        .push(VERSIONS[i]);
+                    found += 1;
+                }
+            }
+            i += 1;
+        }
+
+        error.fail()
+    }
+
+    /// Fail with the feature to enable if the item was left out by the message family features
+    const fn check_disabled(kind: Kind, version: &str, id: &str) {
+        let Some(&(feature, versions)) = find(kind.disabled(), id) else {
+            return;
+        };
+        let mut i = 0;
+        while i < ALL_VERSIONS.len() {
+            if eq(ALL_VERSIONS[i], version) && versions & (1 << i) != 0 {
+                Error::new()
+                    .push(kind.name())
+                    .push(" `")
+                    .push(id)
+                    .push("` in HL7 ")
+                    .push(version)
+                    .push(" is not compiled in; enable the `")
+                    .push(feature)
+                    .push("` feature")
+                    .fail();
+            }
+            i += 1;
+        }
+    }
+
+    pub const fn field(version: &str, id: &str) -> &'static Field {
+        let definition = definition(version);
+        match find(definition.fields, id) {
+            Some(field) => field,
+            None => unknown(Kind::Datatype, version, definition, id),
+        }
+    }
+
+    pub const fn segment(version: &str, id: &str) -> &'static Segment {
+        let definition = definition(version);
+        match find(definition.segments, id) {
+            Some(segment) => segment,
+            None => unknown(Kind::Segment, version, definition, id),
+        }
+    }
+
+    pub const fn message(version: &str, id: &str) -> &'static Message {
+        let definition = definition(version);
+        match find(definition.messages, id) {
+            Some(message) => message,
+            None => unknown(Kind::Message, version, definition, id),
+        }
+    }
+
+    const fn malformed(path: &str) -> ! {
+        Error::new()
+            .push("malformed HL7 path `")
+            .push(path)
+            .push("`; expected something like `PID-5.1.2`")
+            .fail()
+    }
+
+    /// The sub-field at a 1-based position among the components of `parent`'s datatype
+    const fn component(
+        path: &str,
+        parent: &'static SubField,
+        n: usize,
+        name: &str,
+    ) -> &'static SubField {
+        let components: &'static [SubField] = match parent.datatype_definition() {
+            Some(datatype) => datatype.subfields,
+            None => &[],
+        };
+        if n > components.len() {
+            let error = Error::new()
+                .push("HL7 path `")
+                .push(path)
+                .push("` doesn't exist: datatype `")
+                .push(parent.datatype);
+            let error = if components.is_empty() {
+                error.push("` has no ").push(name).push("s")
+            } else {
+                error
+                    .push("` only has ")
+                    .push_number(components.len())
+                    .push(" ")
+                    .push(name)
+                    .push("s")
+            };
+            error.fail();
+        }
+        &components[n - 1]
+    }
+
+    pub const fn path(version: &str, path: &str) -> ResolvedPath {
+        let bytes = path.as_bytes();
+        if bytes.len() < 5 || !(bytes[3] == b'-' || bytes[3] == b'.') {
+            malformed(path);
+        }
+        let mut numbers = [0; 3];
+        let mut count = 0;
+        let mut i = 4;
+        while i <= bytes.len() {
+            if i == bytes.len() || bytes[i] == b'.' {
+                if numbers[count] == 0 {
+                    malformed(path);
+                }
+                count += 1;
+                if count == numbers.len() && i < bytes.len() {
+                    malformed(path);
+                }
+            } else if bytes[i].is_ascii_digit() {
+                numbers[count] = numbers[count] * 10 + (bytes[i] - b'0') as usize;
+            } else {
+                malformed(path);
+            }
+            i += 1;
+        }
+
+        let (id, _) = bytes.split_at(3);
+        let Ok(id) = core::str::from_utf8(id) else {
+            malformed(path);
+        };
+        let segment = segment(version, id);
+        if numbers[0] > segment.fields.len() {
+            Error::new()
+                .push("HL7 path `")
+                .push(path)
+                .push("` doesn't exist: segment `")
+                .push(id)
+                .push("` only has ")
+                .push_number(segment.fields.len())
+                .push(" fields in HL7 ")
+                .push(version)
+                .fail();
+        }
+        let field = &segment.fields[numbers[0] - 1];
+        let component = match count {
+            1 => None,
+            _ => Some(component(path, field, numbers[1], "component")),
+        };
+        let subcomponent = match component {
+            Some(component) if count == 3 => Some(self::component(
+                path,
+                component,
+                numbers[2],
+                "sub-component",
+            )),
+            _ => None,
+        };
+
+        ResolvedPath {
+            segment,
+            field,
+            component,
+            subcomponent,
+        }
+    }
+}
+
+#[cfg(feature = "compressed")]
+pub use runtime::*;
+
+#[cfg(feature = "compressed")]
+mod runtime {
+    use crate::{
+        resolve_path, try_get_field, try_get_message, try_get_segment, Field, Message,
+        ResolvedPath, Segment,
+    };
+
+    pub fn field(version: &str, id: &str) -> &'static Field {
+        try_get_field(version, id).unwrap_or_else(|error| panic!("datatype `{id}`: {error}"))
+    }
+
+    pub fn segment(version: &str, id: &str) -> &'static Segment {
+        try_get_segment(version, id).unwrap_or_else(|error| panic!("segment `{id}`: {error}"))
+    }
+
+    pub fn message(version: &str, id: &str) -> &'static Message {
+        try_get_message(version, id).unwrap_or_else(|error| panic!("message `{id}`: {error}"))
+    }
+
+    pub fn path(version: &str, path: &str) -> ResolvedPath {
+        resolve_path(version, path)
+            .unwrap_or_else(|| panic!("can't resolve HL7 path `{path}` in version {version}"))
+    }
+}
+
+#[cfg(all(test, feature = "251", any(feature = "all-messages", feature = "adt")))]
+mod tests {
+    use crate::{get_field, get_message, get_segment, resolve_path};
+
+    #[test]
+    fn macros_resolve_the_same_definitions() {
+        assert_eq!(
+            crate::hl7_field!("2.5.1", "XPN"),
+            get_field("2.5.1", "XPN").unwrap()
+        );
+        assert_eq!(
+            crate::hl7_segment!("2.5.1", "PID"),
+            get_segment("2.5.1", "PID").unwrap()
+        );
+        assert_eq!(
+            crate::hl7_message!("2.5.1", "ADT_A01"),
+            get_message("2.5.1", "ADT_A01").unwrap()
+        );
+        for path in ["PID-5", "PID-5.1", "PID.5.1.1", "MSH-9.2"] {
+            assert_eq!(
+                crate::macros::path("2.5.1", path),
+                resolve_path("2.5.1", path).unwrap()
+            );
+        }
+        let path = crate::hl7_path!("2.5.1", "PID-5.1.1");
+        assert_eq!(path.target().description, "Surname");
+    }
+
+    #[test]
+    #[should_panic(expected = "did you mean")]
+    fn suggests_similar_segments() {
+        crate::macros::segment("2.5.1", "PIX");
+    }
+
+    #[test]
+    #[should_panic(expected = "HL7 path `PID-999`")]
+    fn rejects_positions_past_the_end() {
+        crate::macros::path("2.5.1", "PID-999");
+    }
+}
@@ -0,0 +1,193 @@
+use core::fmt::Display;
+
+use crate::{get_field, get_segment, Segment, SubField};
+
+/// A location within a segment in the usual HL7 notation, such as `PID-5` (a field), `PID-5.1`
+/// (a component) or `PID-5.1.1` (a sub-component)
+///
+/// Field, component and sub-component numbers are 1-based, as they are in the standard.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let path = FieldPath::parse("PID-5.1").expect("can parse path");
+/// assert_eq!(path.segment, "PID");
+/// assert_eq!(path.field, 5);
+/// assert_eq!(path.component, Some(1));
+/// assert_eq!(path.subcomponent, None);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct FieldPath<'a> {
+    /// The segment ID (`PID`)
+    pub segment: &'a str,
+    /// The field number within the segment
+    pub field: usize,
+    /// The component number within the field, if the path refers to a component
+    pub component: Option<usize>,
+    /// The sub-component number within the component, if the path refers to a sub-component
+    pub subcomponent: Option<usize>,
+}
+
+impl<'a> FieldPath<'a> {
+    /// Parse a path such as `PID-5.1.2`. A `.` is also accepted between the segment ID and the
+    /// field number (`PID.5.1.2`).
+    ///
+    /// Returns `None` if the path is malformed or any of its numbers are `0`.
+    pub fn parse(path: &'a str) -> Option<FieldPath<'a>> {
+        let (_, path) = split_path(path)?;
+        (path.segment.len() == 3).then_some(path)
+    }
+
+    /// Resolve the path to its definitions in the given version
+    pub(crate) fn resolve(&self, version: &str) -> Option<ResolvedPath> {
+        let segment = get_segment(version, self.segment)?;
+        let field = segment.fields.get(self.field - 1)?;
+
+        let component = match self.component {
+            Some(component) => {
+                let datatype = get_field(version, field.datatype)?;
+                Some(datatype.subfields.get(component - 1)?)
+            }
+            None => None,
+        };
+        let subcomponent = match (component, self.subcomponent) {
+            (Some(component), Some(subcomponent)) => {
+                let datatype = get_field(version, component.datatype)?;
+                Some(datatype.subfields.get(subcomponent - 1)?)
+            }
+            _ => None,
+        };
+
+        Some(ResolvedPath {
+            segment,
+            field,
+            component,
+            subcomponent,
+        })
+    }
+}
+
+/// Split a path such as `PID-5.1.2` into the separator after the ID (`-` or `.`) and its parts,
+/// without checking that the ID could be a segment ID
+pub(crate) fn split_path(path: &str) -> Option<(char, FieldPath<'_>)> {
+    let path = path.trim();
+    let split = path.find(['-', '.'])?;
+    let (segment, separator, rest) = (&path[..split], path.as_bytes()[split], &path[split + 1..]);
+    if segment.is_empty() || !segment.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return None;
+    }
+
+    let mut numbers = rest.split('.').map(|n| match n.parse::<usize>() {
+        Ok(0) | Err(_) => None,
+        Ok(n) => Some(n),
+    });
+    let field = numbers.next()??;
+    let component = numbers.next().map(|n| n.ok_or(())).transpose().ok()?;
+    let subcomponent = numbers.next().map(|n| n.ok_or(())).transpose().ok()?;
+    if numbers.next().is_some() {
+        return None;
+    }
+
+    let path = FieldPath {
+        segment,
+        field,
+        component,
+        subcomponent,
+    };
+    Some((char::from(separator), path))
+}
+
+impl Display for FieldPath<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}-{}", self.segment, self.field)?;
+        if let Some(component) = self.component {
+            write!(f, ".{component}")?;
+        }
+        if let Some(subcomponent) = self.subcomponent {
+            write!(f, ".{subcomponent}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The definitions that a [FieldPath] refers to in a given version
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ResolvedPath {
+    /// The segment the path is in
+    pub segment: &'static Segment,
+    /// The field within the segment
+    pub field: &'static SubField,
+    /// The component within the field, if the path refers to a component
+    pub component: Option<&'static SubField>,
+    /// The sub-component within the component, if the path refers to a sub-component
+    pub subcomponent: Option<&'static SubField>,
+}
+
+impl ResolvedPath {
+    /// The most specific definition the path refers to
+    pub fn target(&self) -> &'static SubField {
+        self.subcomponent.or(self.component).unwrap_or(self.field)
+    }
+}
+
+/// Resolve a path such as `PID-5.1` to its definitions in the given version
+///
+/// Returns `None` if the path can't be parsed or doesn't exist in that version.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let resolved = resolve_path("2.5.1", "MSH-9.2").expect("can resolve path");
+/// assert_eq!(resolved.field.description, "Message Type");
+/// assert_eq!(resolved.target().description, "Trigger Event");
+/// ```
+pub fn resolve_path(version: &str, path: &str) -> Option<ResolvedPath> {
+    FieldPath::parse(path)?.resolve(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_paths() {
+        assert_eq!(
+            FieldPath::parse("PID.5.1.2"),
+            Some(FieldPath {
+                segment: "PID",
+                field: 5,
+                component: Some(1),
+                subcomponent: Some(2),
+            })
+        );
+        assert_eq!(FieldPath::parse("MSH-9").unwrap().to_string(), "MSH-9");
+        assert_eq!(
+            FieldPath::parse(" PID-5.1 ").unwrap().to_string(),
+            "PID-5.1"
+        );
+        assert!(FieldPath::parse("PID").is_none());
+        assert!(FieldPath::parse("PID-").is_none());
+        assert!(FieldPath::parse("PID-0").is_none());
+        assert!(FieldPath::parse("PID-5.").is_none());
+        assert!(FieldPath::parse("PID-5.1.2.3").is_none());
+        assert!(FieldPath::parse("PIDX-5").is_none());
+    }
+
+    #[test]
+    fn can_resolve_paths() {
+        let field = resolve_path("2.5.1", "PID-5").expect("can resolve PID-5");
+        assert_eq!(field.field.datatype, "XPN");
+        assert!(field.component.is_none());
+        assert_eq!(field.target(), field.field);
+
+        let subcomponent = resolve_path("2.5.1", "PID-5.1.1").expect("can resolve PID-5.1.1");
+        assert_eq!(subcomponent.component.unwrap().datatype, "FN");
+        assert_eq!(subcomponent.target().description, "Surname");
+
+        assert!(resolve_path("2.5.1", "PID-999").is_none());
+        assert!(resolve_path("2.5.1", "PID-5.99").is_none());
+        assert!(resolve_path("2.5.1", "ZZZ-1").is_none());
+    }
+}
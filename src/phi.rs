@@ -0,0 +1,619 @@
+use core::fmt::Display;
+
+use crate::{get_definition, SubField};
+
+/// A HIPAA Safe Harbor identifier category
+///
+/// These are the kinds of identifiers that Safe Harbor de-identification requires to be removed
+/// for the patient and their relatives, employers and household members.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum PhiCategory {
+    /// Names
+    Name,
+    /// Geographic subdivisions smaller than a state, such as street addresses, cities and ZIP
+    /// codes
+    Geographic,
+    /// Dates (other than years) directly related to an individual, such as birth, admission
+    /// and death dates
+    Date,
+    /// Telephone and fax numbers
+    Telephone,
+    /// Email addresses
+    Email,
+    /// Social security numbers
+    Ssn,
+    /// Medical record numbers and other patient identifiers
+    MedicalRecordNumber,
+    /// Health plan beneficiary numbers
+    HealthPlanNumber,
+    /// Account numbers
+    AccountNumber,
+    /// Certificate and license numbers, such as driver's licenses
+    LicenseNumber,
+    /// Any other unique identifying number or code
+    OtherIdentifier,
+}
+
+impl Display for PhiCategory {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PhiCategory::Name => write!(f, "name"),
+            PhiCategory::Geographic => write!(f, "geographic subdivision"),
+            PhiCategory::Date => write!(f, "date"),
+            PhiCategory::Telephone => write!(f, "telephone number"),
+            PhiCategory::Email => write!(f, "email address"),
+            PhiCategory::Ssn => write!(f, "social security number"),
+            PhiCategory::MedicalRecordNumber => write!(f, "medical record number"),
+            PhiCategory::HealthPlanNumber => write!(f, "health plan beneficiary number"),
+            PhiCategory::AccountNumber => write!(f, "account number"),
+            PhiCategory::LicenseNumber => write!(f, "certificate or license number"),
+            PhiCategory::OtherIdentifier => write!(f, "other identifier"),
+        }
+    }
+}
+
+impl PhiCategory {
+    /// The category of a component of a field in this category, or `None` if the component
+    /// doesn't identify anyone
+    ///
+    /// Most datatypes are identifying as a whole, but some mix identifying components with
+    /// codes that aren't: only the ID number of a `CX` identifies the patient, and the state
+    /// and country of an `XAD` are allowed under Safe Harbor.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hl7_definitions::*;
+    /// let address = PhiCategory::Geographic;
+    /// assert_eq!(address.component("XAD", 3), Some(PhiCategory::Geographic));
+    /// assert_eq!(address.component("XAD", 4), None);
+    /// assert_eq!(PhiCategory::Telephone.component("XTN", 4), Some(PhiCategory::Email));
+    /// ```
+    pub fn component(self, datatype: &str, component: usize) -> Option<PhiCategory> {
+        if datatype == "XTN" && component == 4 {
+            return Some(PhiCategory::Email);
+        }
+        match IDENTIFYING_COMPONENTS
+            .iter()
+            .find(|(id, _)| *id == datatype)
+        {
+            Some((_, components)) if !components.contains(&component) => None,
+            _ => Some(self),
+        }
+    }
+}
+
+/// The identifying components of datatypes which aren't identifying as a whole
+const IDENTIFYING_COMPONENTS: &[(&str, &[usize])] = &[
+    ("AD", &[1, 2, 3, 5, 8]),
+    ("CX", &[1]),
+    ("DLN", &[1]),
+    ("PN", &[1, 2, 3, 4, 5, 6]),
+    ("TS", &[1]),
+    ("XAD", &[1, 2, 3, 5, 8, 9, 10]),
+    ("XPN", &[1, 2, 3, 4, 5, 6]),
+    ("XTN", &[1, 4, 5, 6, 7, 8, 12]),
+];
+
+/// Segment fields holding identifiers of the patient, their relatives or guarantors, and the
+/// dates of their care
+///
+/// Each entry has a word from the field's description, which the tests check against every
+/// compiled-in version so that an entry can't end up on a different field when a segment
+/// changes.
+const PHI_FIELDS: &[(&str, usize, &str, PhiCategory)] = &[
+    ("AL1", 6, "date", PhiCategory::Date),
+    ("DG1", 5, "date", PhiCategory::Date),
+    ("EVN", 2, "recorded", PhiCategory::Date),
+    ("EVN", 6, "occurred", PhiCategory::Date),
+    ("GT1", 2, "number", PhiCategory::AccountNumber),
+    ("GT1", 3, "name", PhiCategory::Name),
+    ("GT1", 4, "spouse", PhiCategory::Name),
+    ("GT1", 5, "address", PhiCategory::Geographic),
+    ("GT1", 6, "home", PhiCategory::Telephone),
+    ("GT1", 7, "business", PhiCategory::Telephone),
+    ("GT1", 8, "birth", PhiCategory::Date),
+    ("GT1", 12, "ssn", PhiCategory::Ssn),
+    ("IN1", 16, "insured", PhiCategory::Name),
+    ("IN1", 18, "birth", PhiCategory::Date),
+    ("IN1", 19, "address", PhiCategory::Geographic),
+    ("IN1", 36, "policy", PhiCategory::HealthPlanNumber),
+    ("IN1", 49, "insured", PhiCategory::HealthPlanNumber),
+    ("IN2", 1, "employee", PhiCategory::OtherIdentifier),
+    ("IN2", 2, "social security", PhiCategory::Ssn),
+    ("IN2", 6, "medicare", PhiCategory::HealthPlanNumber),
+    ("IN2", 7, "medicaid", PhiCategory::Name),
+    ("IN2", 8, "medicaid", PhiCategory::HealthPlanNumber),
+    ("IN2", 61, "member", PhiCategory::HealthPlanNumber),
+    ("IN2", 63, "home", PhiCategory::Telephone),
+    ("MRG", 1, "prior patient", PhiCategory::MedicalRecordNumber),
+    (
+        "MRG",
+        2,
+        "prior alternate patient",
+        PhiCategory::MedicalRecordNumber,
+    ),
+    ("MRG", 3, "account", PhiCategory::AccountNumber),
+    ("MRG", 4, "prior patient", PhiCategory::MedicalRecordNumber),
+    ("MRG", 5, "visit", PhiCategory::AccountNumber),
+    ("MRG", 6, "visit", PhiCategory::AccountNumber),
+    ("MRG", 7, "name", PhiCategory::Name),
+    ("NK1", 2, "name", PhiCategory::Name),
+    ("NK1", 4, "address", PhiCategory::Geographic),
+    ("NK1", 5, "phone", PhiCategory::Telephone),
+    ("NK1", 6, "business", PhiCategory::Telephone),
+    ("NK1", 16, "birth", PhiCategory::Date),
+    ("NK1", 30, "contact", PhiCategory::Name),
+    ("NK1", 31, "contact", PhiCategory::Telephone),
+    ("NK1", 32, "contact", PhiCategory::Geographic),
+    ("NK1", 33, "identifier", PhiCategory::OtherIdentifier),
+    ("NK1", 37, "social security", PhiCategory::Ssn),
+    ("OBR", 7, "observation", PhiCategory::Date),
+    ("OBR", 8, "end", PhiCategory::Date),
+    ("OBR", 14, "received", PhiCategory::Date),
+    ("OBX", 14, "observation", PhiCategory::Date),
+    ("PD1", 7, "duplicate", PhiCategory::MedicalRecordNumber),
+    ("PID", 2, "patient id", PhiCategory::MedicalRecordNumber),
+    ("PID", 3, "patient id", PhiCategory::MedicalRecordNumber),
+    ("PID", 4, "alternate", PhiCategory::MedicalRecordNumber),
+    ("PID", 5, "name", PhiCategory::Name),
+    ("PID", 6, "maiden", PhiCategory::Name),
+    ("PID", 7, "birth", PhiCategory::Date),
+    ("PID", 9, "alias", PhiCategory::Name),
+    ("PID", 11, "address", PhiCategory::Geographic),
+    ("PID", 12, "county", PhiCategory::Geographic),
+    ("PID", 13, "home", PhiCategory::Telephone),
+    ("PID", 14, "business", PhiCategory::Telephone),
+    ("PID", 18, "account", PhiCategory::AccountNumber),
+    ("PID", 19, "ssn", PhiCategory::Ssn),
+    ("PID", 20, "driver", PhiCategory::LicenseNumber),
+    ("PID", 21, "mother", PhiCategory::OtherIdentifier),
+    ("PID", 23, "birth", PhiCategory::Geographic),
+    ("PID", 29, "death", PhiCategory::Date),
+    ("PR1", 5, "date", PhiCategory::Date),
+    ("PV1", 19, "visit", PhiCategory::AccountNumber),
+    ("PV1", 44, "admit", PhiCategory::Date),
+    ("PV1", 45, "discharge", PhiCategory::Date),
+    ("PV1", 50, "visit", PhiCategory::AccountNumber),
+];
+
+/// A segment field classified as protected health information, as found by [phi_fields]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PhiField {
+    /// The segment ID (`PID`)
+    pub segment: &'static str,
+    /// The 1-based field number within the segment
+    pub field: usize,
+    /// The field's definition
+    pub definition: &'static SubField,
+    /// What kind of identifier the field holds
+    pub category: PhiCategory,
+}
+
+/// Classify a segment field in the given version
+///
+/// Returns `None` if the field isn't protected health information, or if the version or field
+/// doesn't exist.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// assert_eq!(phi_category("2.5.1", "PID", 5), Some(PhiCategory::Name));
+/// assert_eq!(phi_category("2.5.1", "PID", 8), None);
+/// ```
+pub fn phi_category(version: &str, segment: &str, field: usize) -> Option<PhiCategory> {
+    let definition = get_definition(version)?.segments.get(segment)?;
+    definition.fields.get(field.checked_sub(1)?)?;
+    classify(segment, field)
+}
+
+fn classify(segment: &str, field: usize) -> Option<PhiCategory> {
+    PHI_FIELDS
+        .iter()
+        .find(|(s, f, ..)| *s == segment && *f == field)
+        .map(|(.., category)| *category)
+}
+
+/// Every segment field in the given version that is classified as protected health
+/// information, ordered by segment and field
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let names = phi_fields("2.5.1").filter(|field| field.category == PhiCategory::Name);
+/// assert!(names.map(|field| field.definition.datatype).all(|datatype| datatype == "XPN"));
+/// ```
+pub fn phi_fields(version: &str) -> impl Iterator<Item = PhiField> {
+    let definition = get_definition(version);
+    PHI_FIELDS
+        .iter()
+        .filter_map(move |(segment, field, _, category)| {
+            let (segment, definition) = definition?.segments.get_entry(*segment)?;
+            Some(PhiField {
+                segment,
+                field: *field,
+                definition: definition.fields.get(field - 1)?,
+                category: *category,
+            })
+        })
+}
+
+#[cfg(feature = "alloc")]
+pub use deidentify::*;
+
+#[cfg(feature = "alloc")]
+mod deidentify {
+    use alloc::format;
+    use alloc::string::String;
+    use core::fmt::Write;
+
+    use super::{classify, PhiCategory};
+    use crate::convert::{segments, Separators};
+    use crate::{try_get_definition, ConvertError, SubField};
+
+    /// What [deidentify] does with values of a [PhiCategory]
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub enum DeidAction {
+        /// Leave the values as they are
+        Keep,
+        /// Empty the whole field
+        Remove,
+        /// Replace each identifying value with `***`
+        Mask,
+        /// Replace each identifying value with a keyed hash (SipHash-2-4, as 16 hex digits), so
+        /// the same value is always replaced the same way without revealing it. The key should
+        /// be kept secret, as identifiers like SSNs are easily guessed otherwise.
+        Hash([u8; 16]),
+        /// Move dates by the given number of days, keeping their precision; values that aren't
+        /// dates are masked
+        ShiftDate(i32),
+    }
+
+    /// De-identify an ER7-encoded message, applying the action the policy chooses for each
+    /// category of protected health information
+    ///
+    /// Fields are classified using the definitions of the given version; components which don't
+    /// identify anyone (see [PhiCategory::component]) are left alone. Segments and fields the
+    /// version doesn't define are passed through unchanged, so locally-defined `Z` segments
+    /// need handling separately.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hl7_definitions::*;
+    /// let message = "MSH|^~\\&|A\rPID|1||12345^^^MRN||DOE^JANE||19800229|F";
+    /// let deidentified = deidentify(message, "2.5.1", |category| match category {
+    ///     PhiCategory::Date => DeidAction::ShiftDate(-30),
+    ///     PhiCategory::MedicalRecordNumber => DeidAction::Hash(*b"sixteen byte key"),
+    ///     _ => DeidAction::Mask,
+    /// })
+    /// .expect("can de-identify");
+    /// assert!(deidentified.ends_with("^^^MRN||***^***||19800130|F"));
+    /// ```
+    pub fn deidentify<'v>(
+        message: &str,
+        version: &'v str,
+        mut policy: impl FnMut(PhiCategory) -> DeidAction,
+    ) -> Result<String, ConvertError<'v>> {
+        let definition = try_get_definition(version)?;
+        let separators = Separators::parse(message).ok_or(ConvertError::MissingHeader)?;
+        let mut deidentifier = Deidentifier {
+            separators,
+            out: String::with_capacity(message.len()),
+        };
+
+        for segment in segments(message, separators) {
+            if segment.number > 1 {
+                deidentifier.out.push('\r');
+            }
+            let id = segment.id;
+            let Some(fields) = definition.segments.get(id).map(|s| s.fields) else {
+                deidentifier.out.push_str(segment.text);
+                continue;
+            };
+
+            deidentifier.out.push_str(id);
+            for (field, text) in segment.fields() {
+                deidentifier.out.push(separators.field);
+                let classified = classify(id, field).zip(fields.get(field - 1));
+                match classified
+                    .map(|(category, definition)| (policy(category), category, definition))
+                {
+                    None | Some((DeidAction::Keep, _, _)) => deidentifier.out.push_str(text),
+                    Some((DeidAction::Remove, _, _)) => {}
+                    Some((action, category, definition)) => {
+                        deidentifier.field(text, definition, category, action)
+                    }
+                }
+            }
+        }
+        Ok(deidentifier.out)
+    }
+
+    struct Deidentifier {
+        separators: Separators,
+        out: String,
+    }
+
+    impl Deidentifier {
+        fn field(
+            &mut self,
+            text: &str,
+            definition: &'static SubField,
+            category: PhiCategory,
+            action: DeidAction,
+        ) {
+            let repetition = self.separators.repetition;
+            for (r, text) in text.split(repetition).enumerate() {
+                if r > 0 {
+                    self.out.push(repetition);
+                }
+                self.value(text, definition, category, action, 0);
+            }
+        }
+
+        /// De-identify a repetition (depth 0), component (1) or sub-component (2)
+        fn value(
+            &mut self,
+            text: &str,
+            definition: &'static SubField,
+            category: PhiCategory,
+            action: DeidAction,
+            depth: usize,
+        ) {
+            let separator = match depth {
+                0 => self.separators.component,
+                1 => self.separators.subcomponent,
+                _ => return self.primitive(text, category, action),
+            };
+            let components = definition
+                .datatype_definition()
+                .map(|field| field.subfields)
+                .unwrap_or_default();
+            if components.is_empty() {
+                return self.primitive(text, category, action);
+            }
+
+            for (i, text) in text.split(separator).enumerate() {
+                if i > 0 {
+                    self.out.push(separator);
+                }
+                let component = category
+                    .component(definition.datatype, i + 1)
+                    .zip(components.get(i));
+                match component {
+                    Some((category, definition)) => {
+                        self.value(text, definition, category, action, depth + 1)
+                    }
+                    None => self.out.push_str(text),
+                }
+            }
+        }
+
+        fn primitive(&mut self, text: &str, category: PhiCategory, action: DeidAction) {
+            if text.is_empty() {
+                return;
+            }
+            match action {
+                DeidAction::Keep => self.out.push_str(text),
+                DeidAction::Remove => {}
+                DeidAction::Hash(key) => {
+                    let _ = write!(self.out, "{:016x}", siphash(&key, text.as_bytes()));
+                }
+                DeidAction::ShiftDate(days) if category == PhiCategory::Date => {
+                    match shift_date(text, days) {
+                        Some(shifted) => self.out.push_str(&shifted),
+                        None => self.out.push_str("***"),
+                    }
+                }
+                DeidAction::Mask | DeidAction::ShiftDate(_) => self.out.push_str("***"),
+            }
+        }
+    }
+
+    /// Shift an HL7 date or date/time (`YYYY[MM[DD[HHMM[SS[.S]]]]][+/-ZZZZ]`) by a number of
+    /// days, keeping everything after the date as it is. Years alone are kept, as Safe Harbor
+    /// allows them; years and months are shifted as if they were the middle of the month.
+    pub(super) fn shift_date(value: &str, days: i32) -> Option<String> {
+        let digits = value.bytes().take_while(u8::is_ascii_digit).count();
+        let number = |range: core::ops::Range<usize>| value.get(range)?.parse::<i64>().ok();
+        let year = number(0..4)?;
+        let (month, day, rest) = match digits {
+            4 => return Some(value.into()),
+            6 => (number(4..6)?, 15, None),
+            _ if digits >= 8 => (number(4..6)?, number(6..8)?, Some(&value[8..])),
+            _ => return None,
+        };
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+
+        let (year, month, day) = civil_from_days(days_from_civil(year, month, day) + days as i64);
+        if !(0..=9999).contains(&year) {
+            return None;
+        }
+        Some(match rest {
+            Some(rest) => format!("{year:04}{month:02}{day:02}{rest}"),
+            None => format!("{year:04}{month:02}{}", &value[6..]),
+        })
+    }
+
+    /// Days since 1970-01-01 in the proleptic Gregorian calendar
+    fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146097 + day_of_era - 719468
+    }
+
+    /// The inverse of [days_from_civil]
+    fn civil_from_days(days: i64) -> (i64, i64, i64) {
+        let days = days + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days - era * 146097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        (year, month, day)
+    }
+
+    /// SipHash-2-4 of `data` under `key`
+    pub(super) fn siphash(key: &[u8; 16], data: &[u8]) -> u64 {
+        fn round(v: &mut [u64; 4]) {
+            v[0] = v[0].wrapping_add(v[1]);
+            v[1] = v[1].rotate_left(13) ^ v[0];
+            v[0] = v[0].rotate_left(32);
+            v[2] = v[2].wrapping_add(v[3]);
+            v[3] = v[3].rotate_left(16) ^ v[2];
+            v[0] = v[0].wrapping_add(v[3]);
+            v[3] = v[3].rotate_left(21) ^ v[0];
+            v[2] = v[2].wrapping_add(v[1]);
+            v[1] = v[1].rotate_left(17) ^ v[2];
+            v[2] = v[2].rotate_left(32);
+        }
+        fn compress(v: &mut [u64; 4], m: u64) {
+            v[3] ^= m;
+            round(v);
+            round(v);
+            v[0] ^= m;
+        }
+
+        let k0 = u64::from_le_bytes(key[..8].try_into().expect("8 bytes"));
+        let k1 = u64::from_le_bytes(key[8..].try_into().expect("8 bytes"));
+        let mut v = [
+            k0 ^ 0x736f6d6570736575,
+            k1 ^ 0x646f72616e646f6d,
+            k0 ^ 0x6c7967656e657261,
+            k1 ^ 0x7465646279746573,
+        ];
+        let mut chunks = data.chunks_exact(8);
+        for chunk in chunks.by_ref() {
+            compress(
+                &mut v,
+                u64::from_le_bytes(chunk.try_into().expect("8 bytes")),
+            );
+        }
+        let mut last = [0; 8];
+        last[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+        compress(
+            &mut v,
+            u64::from_le_bytes(last) | ((data.len() as u64) << 56),
+        );
+        v[2] ^= 0xff;
+        for _ in 0..4 {
+            round(&mut v);
+        }
+        v[0] ^ v[1] ^ v[2] ^ v[3]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "alloc")]
+    use super::deidentify::{shift_date, siphash};
+    use super::*;
+    use crate::{definitions, get_segment};
+
+    #[test]
+    fn classifies_fields_that_exist() {
+        assert_eq!(phi_category("2.5.1", "PID", 7), Some(PhiCategory::Date));
+        assert_eq!(phi_category("2.5.1", "PID", 0), None);
+        assert_eq!(phi_category("2.5.1", "ZPI", 1), None);
+        assert_eq!(phi_category("2.5.1-US", "PID", 5), None);
+
+        for field in phi_fields("2.5.1") {
+            let segment = get_segment("2.5.1", field.segment).expect("segment exists");
+            assert!(core::ptr::eq(
+                field.definition,
+                &segment.fields[field.field - 1]
+            ));
+        }
+        assert!(PHI_FIELDS
+            .windows(2)
+            .all(|w| (w[0].0, w[0].1) < (w[1].0, w[1].1)));
+    }
+
+    #[test]
+    fn fields_match_their_descriptions() {
+        let normalise = |s: &str| {
+            s.chars()
+                .filter(char::is_ascii_alphanumeric)
+                .map(|c| c.to_ascii_lowercase())
+                .collect::<String>()
+        };
+        for (version, definition) in definitions() {
+            for (segment, field, word, _) in PHI_FIELDS {
+                let Some(definition) = definition
+                    .segments
+                    .get(segment)
+                    .and_then(|s| s.fields.get(field - 1))
+                else {
+                    continue;
+                };
+                assert!(
+                    normalise(definition.description).contains(&normalise(word)),
+                    "{segment}-{field} in {version} is {:?}, not a field about {word:?}",
+                    definition.description
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn deidentifies_messages() {
+        let message = "MSH|^~\\&|A|B\rPID|1||12345^^^MRN~678^^^SSN||DOE^JANE^^^^^L||19800301|F|||1 MAIN ST^^TOWN^ON^A1A1A1^CA\rZPI|DOE";
+        let deidentified = deidentify(message, "2.5.1", |category| match category {
+            PhiCategory::Date => DeidAction::ShiftDate(-1),
+            PhiCategory::MedicalRecordNumber => DeidAction::Hash([0; 16]),
+            PhiCategory::Geographic => DeidAction::Remove,
+            _ => DeidAction::Mask,
+        })
+        .expect("can de-identify");
+        let hash = |value: &str| alloc::format!("{:016x}", siphash(&[0; 16], value.as_bytes()));
+        assert_eq!(
+            deidentified,
+            alloc::format!(
+                "MSH|^~\\&|A|B\rPID|1||{}^^^MRN~{}^^^SSN||***^***^^^^^L||19800229|F|||\rZPI|DOE",
+                hash("12345"),
+                hash("678")
+            )
+        );
+        assert_eq!(
+            deidentify(message, "2.5.1", |_| DeidAction::Keep).as_deref(),
+            Ok(message)
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn shifts_dates() {
+        assert_eq!(shift_date("20240301", -1).as_deref(), Some("20240229"));
+        assert_eq!(
+            shift_date("202312311200-0500", 1).as_deref(),
+            Some("202401011200-0500")
+        );
+        assert_eq!(shift_date("202402", 30).as_deref(), Some("202403"));
+        assert_eq!(shift_date("2024", 400).as_deref(), Some("2024"));
+        assert_eq!(shift_date("20241301", 1), None);
+        assert_eq!(shift_date("not a date", 1), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn hashes_with_siphash() {
+        let key = core::array::from_fn(|i| i as u8);
+        assert_eq!(siphash(&key, b""), 0x726fdb47dd0e0e31);
+        assert_eq!(siphash(&key, &[0]), 0x74f839c593dc67fd);
+        let fifteen: [u8; 15] = core::array::from_fn(|i| i as u8);
+        assert_eq!(siphash(&key, &fifteen), 0xa129ca6149be45e5);
+    }
+}
@@ -0,0 +1,490 @@
+//! Python bindings, available with the `python` feature
+//!
+//! Definitions are returned as frozen, dataclass-like objects whose attributes mirror the Rust
+//! types, and missing items are returned as `None`. See `pyproject.toml` for building the
+//! extension module with `maturin`.
+
+use pyo3::prelude::*;
+
+use crate::{
+    Field, Message, MessageCompound, MessageSegment, Segment, SubField, Table, TableId, TableValue,
+    VERSIONS,
+};
+
+/// A component or sub-component of a field, or a field within a segment
+#[pyclass(
+    frozen,
+    get_all,
+    skip_from_py_object,
+    name = "SubField",
+    module = "hl7_definitions"
+)]
+#[derive(Clone)]
+struct PySubField {
+    datatype: &'static str,
+    description: &'static str,
+    optionality: String,
+    max_length: Option<usize>,
+    conformance_length: Option<usize>,
+    may_truncate: bool,
+    repeatability: String,
+    table: Option<usize>,
+    item_number: Option<usize>,
+}
+
+#[pymethods]
+impl PySubField {
+    fn __repr__(&self) -> String {
+        format!(
+            "SubField(datatype={:?}, description={:?}, optionality={:?}, max_length={}, conformance_length={}, may_truncate={}, repeatability={:?}, table={}, item_number={})",
+            self.datatype,
+            self.description,
+            self.optionality,
+            repr_option(self.max_length),
+            repr_option(self.conformance_length),
+            if self.may_truncate { "True" } else { "False" },
+            self.repeatability,
+            repr_option(self.table),
+            repr_option(self.item_number),
+        )
+    }
+}
+
+impl From<&'static SubField> for PySubField {
+    fn from(subfield: &'static SubField) -> Self {
+        PySubField {
+            datatype: subfield.datatype,
+            description: subfield.description,
+            optionality: subfield.optionality.to_string(),
+            max_length: subfield.max_length,
+            conformance_length: subfield.conformance_length,
+            may_truncate: subfield.may_truncate(),
+            repeatability: subfield.repeatability.to_string(),
+            table: subfield.table,
+            item_number: subfield.item_number,
+        }
+    }
+}
+
+fn repr_option<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "None".to_string(), |value| value.to_string())
+}
+
+fn subfields(subfields: &'static [SubField]) -> Vec<PySubField> {
+    subfields.iter().map(PySubField::from).collect()
+}
+
+/// A field type (datatype) and its components
+#[pyclass(
+    frozen,
+    get_all,
+    skip_from_py_object,
+    name = "Field",
+    module = "hl7_definitions"
+)]
+#[derive(Clone)]
+struct PyField {
+    id: String,
+    description: &'static str,
+    subfields: Vec<PySubField>,
+}
+
+#[pymethods]
+impl PyField {
+    fn __repr__(&self) -> String {
+        format!(
+            "Field(id={:?}, description={:?}, subfields=[{} sub-fields])",
+            self.id,
+            self.description,
+            self.subfields.len()
+        )
+    }
+}
+
+impl PyField {
+    fn new(id: &str, field: &'static Field) -> Self {
+        PyField {
+            id: id.to_string(),
+            description: field.description,
+            subfields: subfields(field.subfields),
+        }
+    }
+}
+
+/// A segment and its fields
+#[pyclass(
+    frozen,
+    get_all,
+    skip_from_py_object,
+    name = "Segment",
+    module = "hl7_definitions"
+)]
+#[derive(Clone)]
+struct PySegment {
+    id: String,
+    description: &'static str,
+    fields: Vec<PySubField>,
+}
+
+#[pymethods]
+impl PySegment {
+    fn __repr__(&self) -> String {
+        format!(
+            "Segment(id={:?}, description={:?}, fields=[{} fields])",
+            self.id,
+            self.description,
+            self.fields.len()
+        )
+    }
+}
+
+impl PySegment {
+    fn new(id: &str, segment: &'static Segment) -> Self {
+        PySegment {
+            id: id.to_string(),
+            description: segment.description,
+            fields: subfields(segment.fields),
+        }
+    }
+}
+
+/// A set of segments which may appear as alternatives in the same position of a message
+#[pyclass(
+    frozen,
+    get_all,
+    skip_from_py_object,
+    name = "MessageCompound",
+    module = "hl7_definitions"
+)]
+#[derive(Clone)]
+struct PyMessageCompound {
+    name: Option<&'static str>,
+    description: &'static str,
+    min: usize,
+    max: usize,
+}
+
+#[pymethods]
+impl PyMessageCompound {
+    fn __repr__(&self) -> String {
+        format!(
+            "MessageCompound(name={}, description={:?}, min={}, max={})",
+            repr_option(self.name.map(|name| format!("{name:?}"))),
+            self.description,
+            self.min,
+            self.max
+        )
+    }
+}
+
+impl From<&'static MessageCompound> for PyMessageCompound {
+    fn from(compound: &'static MessageCompound) -> Self {
+        PyMessageCompound {
+            name: compound.name,
+            description: compound.description,
+            min: compound.min,
+            max: compound.max,
+        }
+    }
+}
+
+/// A segment (or group of segments) within a message
+#[pyclass(
+    frozen,
+    get_all,
+    skip_from_py_object,
+    name = "MessageSegment",
+    module = "hl7_definitions"
+)]
+#[derive(Clone)]
+struct PyMessageSegment {
+    name: &'static str,
+    description: &'static str,
+    min: usize,
+    max: usize,
+    children: Vec<PyMessageSegment>,
+    compounds: Vec<PyMessageCompound>,
+}
+
+#[pymethods]
+impl PyMessageSegment {
+    fn __repr__(&self) -> String {
+        format!(
+            "MessageSegment(name={:?}, description={:?}, min={}, max={}, children=[{} segments], compounds=[{} compounds])",
+            self.name,
+            self.description,
+            self.min,
+            self.max,
+            self.children.len(),
+            self.compounds.len()
+        )
+    }
+}
+
+impl From<&'static MessageSegment> for PyMessageSegment {
+    fn from(segment: &'static MessageSegment) -> Self {
+        PyMessageSegment {
+            name: segment.name,
+            description: segment.description,
+            min: segment.min,
+            max: segment.max,
+            children: segment
+                .children
+                .unwrap_or_default()
+                .iter()
+                .map(PyMessageSegment::from)
+                .collect(),
+            compounds: segment
+                .compounds
+                .unwrap_or_default()
+                .iter()
+                .map(PyMessageCompound::from)
+                .collect(),
+        }
+    }
+}
+
+/// A message structure and its segments
+#[pyclass(
+    frozen,
+    get_all,
+    skip_from_py_object,
+    name = "Message",
+    module = "hl7_definitions"
+)]
+#[derive(Clone)]
+struct PyMessage {
+    id: String,
+    name: &'static str,
+    description: &'static str,
+    segments: Vec<PyMessageSegment>,
+}
+
+#[pymethods]
+impl PyMessage {
+    fn __repr__(&self) -> String {
+        format!(
+            "Message(id={:?}, name={:?}, description={:?}, segments=[{} segments])",
+            self.id,
+            self.name,
+            self.description,
+            self.segments.len()
+        )
+    }
+}
+
+impl PyMessage {
+    fn new(id: &str, message: &'static Message) -> Self {
+        PyMessage {
+            id: id.to_string(),
+            name: message.name,
+            description: message.description,
+            segments: message
+                .segments
+                .iter()
+                .map(PyMessageSegment::from)
+                .collect(),
+        }
+    }
+}
+
+/// The definitions a path such as `PID-5.1` refers to; `target` is the most specific of them
+#[pyclass(
+    frozen,
+    get_all,
+    skip_from_py_object,
+    name = "ResolvedPath",
+    module = "hl7_definitions"
+)]
+#[derive(Clone)]
+struct PyResolvedPath {
+    path: String,
+    segment: String,
+    field: PySubField,
+    component: Option<PySubField>,
+    subcomponent: Option<PySubField>,
+    target: PySubField,
+}
+
+#[pymethods]
+impl PyResolvedPath {
+    fn __repr__(&self) -> String {
+        format!(
+            "ResolvedPath(path={:?}, target={})",
+            self.path,
+            self.target.__repr__()
+        )
+    }
+}
+
+/// A single value in a table
+#[pyclass(
+    frozen,
+    get_all,
+    skip_from_py_object,
+    name = "TableValue",
+    module = "hl7_definitions"
+)]
+#[derive(Clone)]
+struct PyTableValue {
+    code: &'static str,
+    description: &'static str,
+    status: String,
+    added: Option<&'static str>,
+    removed: Option<&'static str>,
+    notes: Option<&'static str>,
+}
+
+#[pymethods]
+impl PyTableValue {
+    fn __repr__(&self) -> String {
+        format!(
+            "TableValue(code={:?}, description={:?}, status={:?})",
+            self.code, self.description, self.status
+        )
+    }
+}
+
+impl PyTableValue {
+    fn new(code: &'static str, value: &'static TableValue) -> Self {
+        PyTableValue {
+            code,
+            description: value.description,
+            status: value.status.to_string(),
+            added: value.added,
+            removed: value.removed,
+            notes: value.notes,
+        }
+    }
+}
+
+/// An HL7 table and its values
+#[pyclass(
+    frozen,
+    get_all,
+    skip_from_py_object,
+    name = "Table",
+    module = "hl7_definitions"
+)]
+#[derive(Clone)]
+struct PyTable {
+    id: u16,
+    description: &'static str,
+    table_type: Option<String>,
+    values: Vec<PyTableValue>,
+}
+
+#[pymethods]
+impl PyTable {
+    fn __repr__(&self) -> String {
+        format!(
+            "Table(id={}, description={:?}, values=[{} values])",
+            TableId(self.id),
+            self.description,
+            self.values.len()
+        )
+    }
+}
+
+impl From<Table> for PyTable {
+    fn from(table: Table) -> Self {
+        PyTable {
+            id: table.id().0,
+            description: table.description(),
+            table_type: table.table_type().map(|table_type| table_type.to_string()),
+            values: table
+                .entries()
+                .map(|(code, value)| PyTableValue::new(code, value))
+                .collect(),
+        }
+    }
+}
+
+/// A table number, given either as an integer (`1`) or a string (`"0001"`, `"HL70001"`)
+#[derive(FromPyObject)]
+enum TableArg {
+    Number(u16),
+    Name(String),
+}
+
+impl TableArg {
+    fn id(&self) -> Option<TableId> {
+        match self {
+            TableArg::Number(id) => Some(TableId(*id)),
+            TableArg::Name(name) => name.parse().ok(),
+        }
+    }
+}
+
+/// The versions compiled into the module, oldest first
+#[pyfunction]
+fn versions() -> Vec<&'static str> {
+    VERSIONS.to_vec()
+}
+
+/// Get a field (datatype) for the given version, such as `get_field("2.5.1", "XPN")`
+#[pyfunction]
+fn get_field(version: &str, field: &str) -> Option<PyField> {
+    crate::get_field(version, field).map(|definition| PyField::new(field, definition))
+}
+
+/// Get a segment for the given version, such as `get_segment("2.5.1", "PID")`
+#[pyfunction]
+fn get_segment(version: &str, segment: &str) -> Option<PySegment> {
+    crate::get_segment(version, segment).map(|definition| PySegment::new(segment, definition))
+}
+
+/// Get a message for the given version, such as `get_message("2.5.1", "ADT_A01")`
+#[pyfunction]
+fn get_message(version: &str, message: &str) -> Option<PyMessage> {
+    crate::get_message(version, message).map(|definition| PyMessage::new(message, definition))
+}
+
+/// Resolve a path such as `OBX-5` or `PID-5.1` for the given version
+#[pyfunction]
+fn resolve_path(version: &str, path: &str) -> Option<PyResolvedPath> {
+    let parsed = crate::FieldPath::parse(path)?;
+    let resolved = crate::resolve_path(version, path)?;
+    Some(PyResolvedPath {
+        path: parsed.to_string(),
+        segment: parsed.segment.to_string(),
+        field: resolved.field.into(),
+        component: resolved.component.map(PySubField::from),
+        subcomponent: resolved.subcomponent.map(PySubField::from),
+        target: resolved.target().into(),
+    })
+}
+
+/// Get a table and all of its values, such as `get_table("0078")`
+#[pyfunction]
+fn get_table(table: TableArg) -> Option<PyTable> {
+    crate::get_table(table.id()?).map(PyTable::from)
+}
+
+/// Get the description of a single value in a table, such as `table_value(1, "F")`
+#[pyfunction]
+fn table_value(table: TableArg, code: &str) -> Option<&'static str> {
+    crate::get_table(table.id()?)?.value(code)
+}
+
+/// Statically compiled definitions of HL7 messages across the HL7v2 versions
+#[pymodule]
+fn hl7_definitions(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySubField>()?;
+    m.add_class::<PyField>()?;
+    m.add_class::<PySegment>()?;
+    m.add_class::<PyMessageCompound>()?;
+    m.add_class::<PyMessageSegment>()?;
+    m.add_class::<PyMessage>()?;
+    m.add_class::<PyResolvedPath>()?;
+    m.add_class::<PyTableValue>()?;
+    m.add_class::<PyTable>()?;
+    m.add_function(wrap_pyfunction!(versions, m)?)?;
+    m.add_function(wrap_pyfunction!(get_field, m)?)?;
+    m.add_function(wrap_pyfunction!(get_segment, m)?)?;
+    m.add_function(wrap_pyfunction!(get_message, m)?)?;
+    m.add_function(wrap_pyfunction!(resolve_path, m)?)?;
+    m.add_function(wrap_pyfunction!(get_table, m)?)?;
+    m.add_function(wrap_pyfunction!(table_value, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,545 @@
+use core::fmt::Display;
+use core::str::FromStr;
+
+use crate::codegen::{self, TableValues};
+use crate::Version;
+
+/// The values of every table, by number, decoding them first with the `compressed` feature
+#[inline]
+pub(crate) fn table_map() -> &'static phf::Map<u16, &'static TableValues> {
+    #[cfg(not(feature = "compressed"))]
+    return &codegen::TABLES;
+    #[cfg(feature = "compressed")]
+    return crate::compressed::tables();
+}
+
+/// Get the description of the given table index
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// assert_eq!(table_description(3), Some("Event type"));
+/// ```
+#[inline]
+pub fn table_description(table: u16) -> Option<&'static str> {
+    codegen::TABLE_DESCRIPTIONS.get(&table).copied()
+}
+
+/// Get a single value from a table
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let code = String::from("A01");
+/// assert_eq!(table_value(3, &code), Some("ADT/ACK - Admit/visit notification"));
+/// ```
+#[inline]
+pub fn table_value(table: u16, key: &str) -> Option<&'static str> {
+    table_map()
+        .get(&table)
+        .and_then(|table| table.get(key))
+        .map(|value| value.description)
+}
+
+/// Get a single value from a table, along with its metadata
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let value = table_entry(3, "A18").expect("table value exists");
+/// assert_eq!(value.status, ValueStatus::BackwardCompatibility);
+/// ```
+#[inline]
+pub fn table_entry(table: u16, key: &str) -> Option<&'static TableValue> {
+    table_map().get(&table).and_then(|table| table.get(key))
+}
+
+/// Get _all_ the values for a given table
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let values = table_values(7).unwrap();
+/// assert_eq!(values.len(), 7);
+/// assert!(values.iter().all(|(_, value)| value.is_active()));
+/// ```
+#[inline]
+pub fn table_values(table: u16) -> Option<&'static [(&'static str, TableValue)]> {
+    table_map().get(&table).map(|table| table.entries)
+}
+
+/// A single value in a table
+///
+/// The source data has no per-value metadata, so `added`, `removed` and `notes` are only known
+/// for the few values listed in `assets/table_value_meta.json` (currently some of tables 0085,
+/// 0104 and 0292); `status` is otherwise inferred from the description
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TableValue {
+    /// The description of the value
+    pub description: &'static str,
+    /// Whether the value is still in use
+    pub status: ValueStatus,
+    /// The version the value was introduced in, if known
+    pub added: Option<&'static str>,
+    /// The version the value was removed in, if it has been
+    pub removed: Option<&'static str>,
+    /// Any notes on how the value should be used
+    pub notes: Option<&'static str>,
+}
+
+impl TableValue {
+    /// Whether the value is current, i.e. not deprecated, withdrawn, or only retained for
+    /// backwards compatibility
+    pub fn is_active(&self) -> bool {
+        self.status == ValueStatus::Active
+    }
+
+    /// Whether the value was part of the given version, as far as is known. Values with no
+    /// recorded `added` / `removed` versions are assumed to be present in every version.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hl7_definitions::*;
+    /// let value = table_entry(104, "2.5").expect("table value exists");
+    /// assert!(value.is_available_in("2.5.1"));
+    /// assert!(!value.is_available_in("2.4"));
+    /// ```
+    pub fn is_available_in(&self, version: &str) -> bool {
+        let Some(version) = Version::parse(version) else {
+            return false;
+        };
+        let after_added = self
+            .added
+            .and_then(Version::parse)
+            .is_none_or(|added| version >= added);
+        let before_removed = self
+            .removed
+            .and_then(Version::parse)
+            .is_none_or(|removed| version < removed);
+        after_added && before_removed
+    }
+}
+
+/// The lifecycle status of a table value
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ValueStatus {
+    /// The value is in current use
+    Active,
+    /// The value is only retained for backwards compatibility and shouldn't be used in new
+    /// messages
+    BackwardCompatibility,
+    /// The value is deprecated and may be removed in a future version
+    Deprecated,
+    /// The value has been withdrawn from the standard
+    Withdrawn,
+}
+
+impl Display for ValueStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ValueStatus::Active => write!(f, "active"),
+            ValueStatus::BackwardCompatibility => write!(f, "backwards compatibility"),
+            ValueStatus::Deprecated => write!(f, "deprecated"),
+            ValueStatus::Withdrawn => write!(f, "withdrawn"),
+        }
+    }
+}
+
+/// Get whether a table is HL7-defined or user-defined, if that is known
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// assert_eq!(table_type(8), Some(TableType::Hl7));
+/// assert_eq!(table_type(1), Some(TableType::User));
+/// ```
+#[inline]
+pub fn table_type(table: u16) -> Option<TableType> {
+    codegen::TABLE_TYPES.get(&table).copied()
+}
+
+/// Who owns the values of a table
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TableType {
+    /// The values are fixed by the HL7 standard
+    Hl7,
+    /// The values are only suggestions; sites are expected to extend or replace them with
+    /// their own
+    User,
+}
+
+impl Display for TableType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TableType::Hl7 => write!(f, "HL7-defined"),
+            TableType::User => write!(f, "user-defined"),
+        }
+    }
+}
+
+/// How strictly codes should be checked against a table
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum TableValidation {
+    /// Codes missing from user-defined tables are accepted as local values
+    #[default]
+    Lenient,
+    /// Codes must appear in the table regardless of its type
+    Strict,
+}
+
+/// The outcome of checking a code against a table
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CodeCheck {
+    /// The code is in the table; its description is included
+    Known(&'static str),
+    /// The code isn't in the table, but the table is user-defined so it is presumed to be a
+    /// site-specific value
+    Local,
+    /// The code isn't in the table and isn't allowed to be
+    Unknown,
+}
+
+impl CodeCheck {
+    /// Whether the code should be accepted
+    pub fn is_valid(&self) -> bool {
+        !matches!(self, CodeCheck::Unknown)
+    }
+}
+
+/// The number of an HL7 table, such as `0001` (Administrative Sex)
+///
+/// Table numbers are commonly written zero-padded (`0001`) or with an `HL7` prefix
+/// (`HL70001`); both forms can be parsed, as can the bare number.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let id: TableId = "HL70001".parse().expect("can parse table id");
+/// assert_eq!(id, TableId(1));
+/// assert_eq!("0001".parse::<TableId>(), Ok(TableId(1)));
+/// assert_eq!(id.to_string(), "0001");
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct TableId(pub u16);
+
+impl From<u16> for TableId {
+    fn from(id: u16) -> Self {
+        TableId(id)
+    }
+}
+
+impl From<TableId> for u16 {
+    fn from(id: TableId) -> Self {
+        id.0
+    }
+}
+
+impl Display for TableId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:04}", self.0)
+    }
+}
+
+/// The error returned when a string can't be parsed as a [TableId]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ParseTableIdError;
+
+impl Display for ParseTableIdError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "invalid HL7 table number")
+    }
+}
+
+impl core::error::Error for ParseTableIdError {}
+
+impl FromStr for TableId {
+    type Err = ParseTableIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = match s.get(..3) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("HL7") => s[3..].trim_start(),
+            _ => s,
+        };
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseTableIdError);
+        }
+        s.parse::<u16>().map(TableId).map_err(|_| ParseTableIdError)
+    }
+}
+
+/// A single HL7 table, with richer lookups than the plain [table_value] function
+#[derive(Copy, Clone, Debug)]
+pub struct Table {
+    pub(crate) id: TableId,
+    pub(crate) description: &'static str,
+    pub(crate) values: &'static TableValues,
+}
+
+/// Get a table by its number
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let table = get_table(1).expect("table 1 exists");
+/// assert_eq!(table.description(), "Administrative Sex");
+/// assert_eq!(table.value("F"), Some("Female"));
+/// ```
+pub fn get_table<T: Into<TableId>>(table: T) -> Option<Table> {
+    let id = table.into();
+    table_map().get(&id.0).map(|values| Table {
+        id,
+        description: table_description(id.0).unwrap_or_default(),
+        values,
+    })
+}
+
+/// Iterate over every table compiled into the library, in no particular order
+pub fn tables() -> impl Iterator<Item = Table> {
+    table_map().entries().map(|(id, values)| Table {
+        id: TableId(*id),
+        description: table_description(*id).unwrap_or_default(),
+        values,
+    })
+}
+
+impl Table {
+    /// The number of the table
+    pub fn id(&self) -> TableId {
+        self.id
+    }
+
+    /// The description of the table
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+
+    /// Whether the table is HL7-defined or user-defined, if that is known
+    pub fn table_type(&self) -> Option<TableType> {
+        table_type(self.id.0)
+    }
+
+    /// How many values the table has
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Whether the table has no values
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Iterate over all the `(code, description)` pairs in the table
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &'static str)> {
+        self.values
+            .entries
+            .iter()
+            .map(|(code, value)| (*code, value.description))
+    }
+
+    /// Iterate over all the codes in the table along with their metadata
+    pub fn entries(&self) -> impl Iterator<Item = (&'static str, &'static TableValue)> {
+        self.values
+            .entries
+            .iter()
+            .map(|(code, value)| (*code, value))
+    }
+
+    /// Look up the description of a code, exactly as written
+    pub fn value(&self, code: &str) -> Option<&'static str> {
+        self.values.get(code).map(|value| value.description)
+    }
+
+    /// Look up a code, exactly as written, along with its metadata
+    pub fn entry(&self, code: &str) -> Option<&'static TableValue> {
+        self.values.get(code)
+    }
+
+    /// Look up a code ignoring ASCII case, returning the code as it is written in the table
+    /// along with its description
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hl7_definitions::*;
+    /// let table = get_table(8).expect("table 8 exists");
+    /// assert_eq!(table.value_ignore_case("aa").map(|(code, _)| code), Some("AA"));
+    /// ```
+    pub fn value_ignore_case(&self, code: &str) -> Option<(&'static str, &'static str)> {
+        if let Some((code, value)) = self.values.get_entry(code) {
+            return Some((code, value.description));
+        }
+        self.iter().find(|(c, _)| c.eq_ignore_ascii_case(code))
+    }
+
+    /// Find the code whose description matches the given description, ignoring ASCII case and
+    /// surrounding whitespace
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hl7_definitions::*;
+    /// let table = get_table("HL70001".parse::<TableId>().unwrap()).expect("table 1 exists");
+    /// assert_eq!(table.code_for_description("female"), Some("F"));
+    /// ```
+    pub fn code_for_description(&self, description: &str) -> Option<&'static str> {
+        let description = description.trim();
+        self.iter()
+            .find(|(_, d)| d.trim().eq_ignore_ascii_case(description))
+            .map(|(code, _)| code)
+    }
+
+    /// Check whether a code is acceptable for this table
+    ///
+    /// Tables whose type is unknown are treated like HL7-defined tables.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hl7_definitions::*;
+    /// let table = get_table(4).expect("table 4 exists");
+    /// assert_eq!(table.check_code("I", TableValidation::Lenient), CodeCheck::Known("Inpatient"));
+    /// assert_eq!(table.check_code("ZZ", TableValidation::Lenient), CodeCheck::Local);
+    /// assert_eq!(table.check_code("ZZ", TableValidation::Strict), CodeCheck::Unknown);
+    /// ```
+    pub fn check_code(&self, code: &str, validation: TableValidation) -> CodeCheck {
+        match (self.value(code), validation, self.table_type()) {
+            (Some(description), _, _) => CodeCheck::Known(description),
+            (None, TableValidation::Lenient, Some(TableType::User)) => CodeCheck::Local,
+            (None, _, _) => CodeCheck::Unknown,
+        }
+    }
+
+    /// Iterate over the `(code, description)` pairs whose description contains `needle`,
+    /// ignoring ASCII case
+    pub fn find_by_description<'a>(
+        &self,
+        needle: &'a str,
+    ) -> impl Iterator<Item = (&'static str, &'static str)> + 'a {
+        self.iter()
+            .filter(move |(_, description)| contains_ignore_ascii_case(description, needle))
+    }
+}
+
+fn contains_ignore_ascii_case(haystack: &str, needle: &str) -> bool {
+    let (haystack, needle) = (haystack.as_bytes(), needle.as_bytes());
+    needle.is_empty()
+        || haystack
+            .windows(needle.len())
+            .any(|window| window.eq_ignore_ascii_case(needle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_table_ids() {
+        assert_eq!("1".parse::<TableId>(), Ok(TableId(1)));
+        assert_eq!("0078".parse::<TableId>(), Ok(TableId(78)));
+        assert_eq!("HL70396".parse::<TableId>(), Ok(TableId(396)));
+        assert_eq!("hl7 0396".parse::<TableId>(), Ok(TableId(396)));
+        assert_eq!("".parse::<TableId>(), Err(ParseTableIdError));
+        assert_eq!("HL7".parse::<TableId>(), Err(ParseTableIdError));
+        assert_eq!("+1".parse::<TableId>(), Err(ParseTableIdError));
+        assert_eq!("99999".parse::<TableId>(), Err(ParseTableIdError));
+    }
+
+    #[test]
+    fn can_lookup_values_with_borrowed_keys() {
+        let buffer = String::from("MSH|^~\\&|||||||ADT^A08");
+        let event = &buffer[buffer.len() - 3..];
+        assert_eq!(
+            table_value(3, event),
+            Some("ADT/ACK -  Update patient information")
+        );
+    }
+
+    #[test]
+    fn can_lookup_by_description() {
+        let table = get_table(TableId(136)).expect("table 136 exists");
+        assert_eq!(table.id(), TableId(136));
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.value_ignore_case("y"), Some(("Y", "Yes")));
+        assert_eq!(table.code_for_description(" NO "), Some("N"));
+        assert_eq!(table.code_for_description("Maybe"), None);
+
+        let table = get_table(203).expect("table 203 exists");
+        let mut licenses = table
+            .find_by_description("LICENSE NUMBER")
+            .map(|(code, _)| code)
+            .collect::<Vec<&str>>();
+        licenses.sort();
+        assert!(licenses.contains(&"DL"));
+        assert!(licenses.contains(&"MD"));
+    }
+
+    #[test]
+    fn can_check_codes_against_table_types() {
+        let ack = get_table(8).expect("table 8 exists");
+        assert_eq!(ack.table_type(), Some(TableType::Hl7));
+        assert!(ack.check_code("AA", TableValidation::default()).is_valid());
+        assert_eq!(
+            ack.check_code("XX", TableValidation::default()),
+            CodeCheck::Unknown
+        );
+
+        let sex = get_table(1).expect("table 1 exists");
+        assert_eq!(sex.table_type(), Some(TableType::User));
+        assert_eq!(
+            sex.check_code("X", TableValidation::default()),
+            CodeCheck::Local
+        );
+        assert!(!sex.check_code("X", TableValidation::Strict).is_valid());
+    }
+
+    #[test]
+    fn every_table_type_refers_to_a_table() {
+        for table in codegen::TABLE_TYPES.keys() {
+            assert!(get_table(*table).is_some(), "table {table} exists");
+        }
+    }
+
+    #[test]
+    fn table_values_carry_metadata() {
+        let partial = table_entry(85, "S").expect("table value exists");
+        assert_eq!(partial.status, ValueStatus::Deprecated);
+        assert!(partial.notes.is_some());
+
+        let merge = table_entry(3, "A18").expect("table value exists");
+        assert_eq!(merge.status, ValueStatus::BackwardCompatibility);
+        assert!(!merge.is_active());
+        assert_eq!(
+            table_value(3, "A18"),
+            Some("ADT/ACK -  Merge patient information (for backward compatibility only)")
+        );
+
+        let admit = table_entry(3, "A01").expect("table value exists");
+        assert!(admit.is_active());
+        assert!(admit.is_available_in("2.1"));
+        assert!(!admit.is_available_in("garbage"));
+
+        let v231 = table_entry(104, "2.3.1").expect("table value exists");
+        assert!(!v231.is_available_in("2.3"));
+        assert!(v231.is_available_in("2.3.1"));
+    }
+
+    #[test]
+    fn can_iterate_tables() {
+        assert_eq!(tables().count(), table_map().len());
+        assert!(tables().any(|table| table.id() == TableId(1)));
+    }
+}
@@ -0,0 +1,344 @@
+use core::fmt::Display;
+
+use crate::{codegen, Definition};
+
+pub use codegen::{ALL_VERSIONS, VERSIONS};
+
+/// A parsed HL7 version number, such as `2.5.1`
+///
+/// Versions are ordered numerically, so `2.3.1` sorts between `2.3` and `2.4`. Missing
+/// components are treated as `0`, so `2.5` and `2.5.0` compare equal.
+#[derive(Copy, Clone, Debug)]
+pub struct Version {
+    parts: [u16; Version::MAX_PARTS],
+    len: u8,
+}
+
+impl Version {
+    /// The maximum number of dotted components a version can have (`2.3.1.1`)
+    pub const MAX_PARTS: usize = 4;
+
+    /// Parse a version string, being forgiving of the things real-world `MSH-12` values
+    /// contain: surrounding whitespace, and trailing qualifiers such as `-US` are ignored.
+    ///
+    /// Returns `None` if the string doesn't start with a number.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hl7_definitions::*;
+    /// let version = Version::parse("2.5.1-US ").expect("can parse version");
+    /// assert_eq!(version.to_string(), "2.5.1");
+    /// assert!(Version::parse("abc").is_none());
+    /// ```
+    pub fn parse(version: &str) -> Option<Version> {
+        let mut parts = [0; Version::MAX_PARTS];
+        let mut len = 0;
+        for part in version.trim().split('.') {
+            let digits = part
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(part.len());
+            let Ok(n) = part[..digits].parse::<u16>() else {
+                break;
+            };
+            parts[len] = n;
+            len += 1;
+            if digits != part.len() || len == Version::MAX_PARTS {
+                break;
+            }
+        }
+
+        if len == 0 {
+            return None;
+        }
+        Some(Version {
+            parts,
+            len: len as u8,
+        })
+    }
+
+    /// The dotted components of the version
+    pub fn parts(&self) -> &[u16] {
+        &self.parts[..self.len as usize]
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.parts == other.parts
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.parts.cmp(&other.parts)
+    }
+}
+
+impl core::hash::Hash for Version {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.parts.hash(state);
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (i, part) in self.parts().iter().enumerate() {
+            if i > 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{part}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether definitions for the given version are compiled into the library
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// assert!(is_version_enabled("2.5.1"));
+/// assert!(!is_version_enabled("2.5.1.1"));
+/// ```
+pub fn is_version_enabled(version: &str) -> bool {
+    VERSIONS.contains(&version)
+}
+
+/// Whether the given version is one the library knows about, regardless of whether its
+/// feature is enabled
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// assert!(is_version_known("2.3.1"));
+/// assert!(!is_version_known("3.0"));
+/// ```
+pub fn is_version_known(version: &str) -> bool {
+    ALL_VERSIONS.contains(&version)
+}
+
+/// Find the compiled-in version that best matches an arbitrary version string (such as the
+/// value of `MSH-12`)
+///
+/// An exact match is preferred, followed by the newest enabled version that is older than the
+/// requested one, followed by the oldest enabled version that is newer than it. Returns `None`
+/// if the version can't be parsed or no versions are enabled.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// assert_eq!(nearest_version("2.5.1"), Some("2.5.1"));
+/// assert_eq!(nearest_version("2.3.1.1"), Some("2.3.1"));
+/// ```
+pub fn nearest_version(version: &str) -> Option<&'static str> {
+    resolve_version(version, VersionPolicy::NearestLower)
+        .or_else(|| resolve_version(version, VersionPolicy::NearestHigher))
+        .map(|resolved| resolved.version)
+}
+
+/// How to pick a version when the requested one isn't compiled into the library
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum VersionPolicy {
+    /// Only accept the requested version (after normalization)
+    Exact,
+    /// Fall back to the newest enabled version that is older than the requested one
+    #[default]
+    NearestLower,
+    /// Fall back to the oldest enabled version that is newer than the requested one
+    NearestHigher,
+    /// Fall back to the newest enabled version
+    Latest,
+}
+
+/// Why [resolve_version] chose the version it did
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ResolutionReason {
+    /// The requested version is compiled in as-is
+    Exact,
+    /// The requested version is compiled in once whitespace, trailing qualifiers (`-US`) and
+    /// trailing zero components were stripped
+    Normalized,
+    /// The requested version isn't available, the nearest older version was used instead
+    NearestLower,
+    /// The requested version isn't available, the nearest newer version was used instead
+    NearestHigher,
+    /// The requested version isn't available, the newest version was used instead
+    Latest,
+}
+
+impl Display for ResolutionReason {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ResolutionReason::Exact => write!(f, "exact match"),
+            ResolutionReason::Normalized => write!(f, "matched after normalization"),
+            ResolutionReason::NearestLower => write!(f, "fell back to nearest older version"),
+            ResolutionReason::NearestHigher => write!(f, "fell back to nearest newer version"),
+            ResolutionReason::Latest => write!(f, "fell back to latest version"),
+        }
+    }
+}
+
+/// The result of resolving an arbitrary version string to a compiled-in definition
+#[derive(Copy, Clone, Debug)]
+pub struct ResolvedVersion {
+    /// The compiled-in version that was chosen
+    pub version: &'static str,
+    /// The definition for the chosen version
+    pub definition: &'static Definition,
+    /// Why this version was chosen
+    pub reason: ResolutionReason,
+}
+
+/// Resolve an arbitrary version string (such as the value of `MSH-12`) to the best available
+/// compiled-in definition
+///
+/// An exact or normalized match is always preferred; the `policy` only decides what happens
+/// when neither is available. Returns `None` if nothing satisfies the policy.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// let resolved = resolve_version("2.3.1.1", VersionPolicy::NearestLower).expect("can resolve");
+/// assert_eq!(resolved.version, "2.3.1");
+/// assert_eq!(resolved.reason, ResolutionReason::NearestLower);
+///
+/// let resolved = resolve_version("2.5.1-US", VersionPolicy::Exact).expect("can resolve");
+/// assert_eq!(resolved.version, "2.5.1");
+/// assert_eq!(resolved.reason, ResolutionReason::Normalized);
+/// ```
+pub fn resolve_version(version: &str, policy: VersionPolicy) -> Option<ResolvedVersion> {
+    let resolved = |version: &'static str, reason| {
+        crate::get_definition(version).map(|definition| ResolvedVersion {
+            version,
+            definition,
+            reason,
+        })
+    };
+
+    if let Some(v) = VERSIONS.iter().find(|v| **v == version) {
+        return resolved(v, ResolutionReason::Exact);
+    }
+
+    let requested = Version::parse(version);
+    let enabled = VERSIONS
+        .iter()
+        .filter_map(|v| Version::parse(v).map(|parsed| (*v, parsed)));
+    let mut lower = None;
+    let mut higher = None;
+    if let Some(requested) = requested {
+        for (v, parsed) in enabled {
+            if parsed == requested {
+                return resolved(v, ResolutionReason::Normalized);
+            } else if parsed < requested {
+                lower = Some(v);
+            } else {
+                higher = Some(v);
+                break;
+            }
+        }
+    }
+
+    match policy {
+        VersionPolicy::Exact => None,
+        VersionPolicy::NearestLower => {
+            lower.and_then(|v| resolved(v, ResolutionReason::NearestLower))
+        }
+        VersionPolicy::NearestHigher => {
+            higher.and_then(|v| resolved(v, ResolutionReason::NearestHigher))
+        }
+        VersionPolicy::Latest => VERSIONS
+            .last()
+            .and_then(|v| resolved(v, ResolutionReason::Latest)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_are_sorted() {
+        let parsed = ALL_VERSIONS
+            .iter()
+            .map(|v| Version::parse(v).expect("can parse version"))
+            .collect::<Vec<Version>>();
+        assert!(parsed.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn enabled_versions_are_known_and_have_definitions() {
+        for version in VERSIONS {
+            assert!(is_version_known(version));
+            assert!(crate::get_definition(version).is_some());
+        }
+        for version in ALL_VERSIONS {
+            assert_eq!(
+                is_version_enabled(version),
+                crate::get_definition(version).is_some()
+            );
+        }
+    }
+
+    #[test]
+    fn can_parse_versions() {
+        assert_eq!(Version::parse("2.3.1.1").unwrap().parts(), &[2, 3, 1, 1]);
+        assert_eq!(Version::parse(" 2.4 ").unwrap().parts(), &[2, 4]);
+        assert_eq!(Version::parse("2.5.1-US").unwrap().parts(), &[2, 5, 1]);
+        assert_eq!(Version::parse("2.5.").unwrap().parts(), &[2, 5]);
+        assert_eq!(Version::parse("2.5"), Version::parse("2.5.0"));
+        assert!(Version::parse("2.3.1") < Version::parse("2.4"));
+        assert!(Version::parse("").is_none());
+        assert!(Version::parse("v2.5").is_none());
+    }
+
+    #[cfg(all(feature = "21", feature = "24", feature = "251", feature = "271"))]
+    #[test]
+    fn can_find_nearest_version() {
+        assert_eq!(nearest_version("2.4 "), Some("2.4"));
+        assert_eq!(nearest_version("2.5.1-US"), Some("2.5.1"));
+        assert_eq!(nearest_version("2.0"), Some("2.1"));
+        assert_eq!(nearest_version("2.9"), Some("2.7.1"));
+        assert_eq!(nearest_version("garbage"), None);
+    }
+
+    #[cfg(all(feature = "21", feature = "24", feature = "251", feature = "271"))]
+    #[test]
+    fn can_resolve_versions_with_policies() {
+        let exact = resolve_version("2.4", VersionPolicy::Exact).expect("can resolve 2.4");
+        assert_eq!(exact.version, "2.4");
+        assert_eq!(exact.reason, ResolutionReason::Exact);
+
+        let trimmed = resolve_version("2.4 ", VersionPolicy::Exact).expect("can resolve 2.4");
+        assert_eq!(trimmed.version, "2.4");
+        assert_eq!(trimmed.reason, ResolutionReason::Normalized);
+
+        assert!(resolve_version("2.3.1.1", VersionPolicy::Exact).is_none());
+        let higher = resolve_version("2.3.1.1", VersionPolicy::NearestHigher).expect("can resolve");
+        assert_eq!(higher.version, "2.4");
+        assert_eq!(higher.reason, ResolutionReason::NearestHigher);
+
+        assert!(resolve_version("2.0", VersionPolicy::NearestLower).is_none());
+        assert!(resolve_version("2.9", VersionPolicy::NearestHigher).is_none());
+
+        let latest = resolve_version("garbage", VersionPolicy::Latest).expect("can resolve");
+        assert_eq!(latest.version, "2.7.1");
+        assert_eq!(latest.reason, ResolutionReason::Latest);
+        assert!(std::ptr::eq(
+            latest.definition,
+            crate::get_definition("2.7.1").unwrap()
+        ));
+    }
+}
@@ -0,0 +1,302 @@
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+use crate::{get_definition, Definition, Message, MessageSegment, Segment, SubField};
+
+/// Where the item currently being visited sits within the message
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct VisitContext<'a> {
+    /// The names of the groups enclosing the item, outermost first
+    pub groups: &'a [&'static str],
+    /// The segment the item is in, if it is a segment or part of one
+    pub segment: Option<&'static str>,
+    /// The 1-based field number, if the item is a field or part of one
+    pub field: Option<usize>,
+    /// The 1-based component number, if the item is a component or part of one
+    pub component: Option<usize>,
+    /// The 1-based sub-component number, if the item is a sub-component
+    pub subcomponent: Option<usize>,
+    /// How deeply the item is nested: top-level segments and groups are at depth 0, and each
+    /// group, segment, field and component adds one level
+    pub depth: usize,
+    /// The minimum number of times the item's segment (or group) must appear in the message,
+    /// taking its enclosing groups into account
+    pub min: usize,
+    /// The maximum number of times the item's segment (or group) may appear in the message,
+    /// taking its enclosing groups into account
+    pub max: usize,
+}
+
+impl<'a> VisitContext<'a> {
+    fn new(
+        groups: &'a [&'static str],
+        segment: Option<&'static str>,
+        depth: usize,
+        min: usize,
+        max: usize,
+    ) -> Self {
+        VisitContext {
+            groups,
+            segment,
+            field: None,
+            component: None,
+            subcomponent: None,
+            depth,
+            min,
+            max,
+        }
+    }
+
+    /// Whether the item must appear in every message
+    pub fn is_required(&self) -> bool {
+        self.min > 0
+    }
+}
+
+impl Display for VisitContext<'_> {
+    /// Formats the context as a path such as `ORDER/OBR-4.1`
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (i, group) in self.groups.iter().enumerate() {
+            if i > 0 {
+                write!(f, "/")?;
+            }
+            write!(f, "{group}")?;
+        }
+        if let Some(segment) = self.segment {
+            if !self.groups.is_empty() {
+                write!(f, "/")?;
+            }
+            write!(f, "{segment}")?;
+        }
+        if let Some(field) = self.field {
+            write!(f, "-{field}")?;
+        }
+        for n in [self.component, self.subcomponent].into_iter().flatten() {
+            write!(f, ".{n}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Callbacks for walking a message structure with [walk_message]
+///
+/// Every method does nothing by default, so visitors only need to implement the parts of the
+/// tree they are interested in.
+pub trait MessageVisitor {
+    /// Called when entering a group of segments, before any of its children are visited
+    fn enter_group(&mut self, group: &'static MessageSegment, context: &VisitContext<'_>) {
+        let _ = (group, context);
+    }
+
+    /// Called after all of a group's children have been visited
+    fn leave_group(&mut self, group: &'static MessageSegment, context: &VisitContext<'_>) {
+        let _ = (group, context);
+    }
+
+    /// Called for each segment in the message, before its fields are visited. `definition` is
+    /// `None` if the segment isn't defined in the version. For a choice position, the fields
+    /// visited are those of the first alternative; see [MessageSegment::alternatives].
+    fn visit_segment(
+        &mut self,
+        segment: &'static MessageSegment,
+        definition: Option<&'static Segment>,
+        context: &VisitContext<'_>,
+    ) {
+        let _ = (segment, definition, context);
+    }
+
+    /// Called for each field of a segment, before its components are visited
+    fn visit_field(&mut self, field: &'static SubField, context: &VisitContext<'_>) {
+        let _ = (field, context);
+    }
+
+    /// Called for each component of a composite field, and for each sub-component of a
+    /// composite component (in which case `context.subcomponent` is set)
+    fn visit_component(&mut self, component: &'static SubField, context: &VisitContext<'_>) {
+        let _ = (component, context);
+    }
+}
+
+/// Walk a message structure depth-first, in order, calling the visitor for every group,
+/// segment, field, component and sub-component
+///
+/// Segment and datatype definitions are looked up in the given version; segments which aren't
+/// defined in it are still visited, but have no fields.
+///
+/// # Example
+///
+/// ```
+/// # use hl7_definitions::*;
+/// struct CountFields(usize);
+///
+/// impl MessageVisitor for CountFields {
+///     fn visit_field(&mut self, _field: &'static SubField, _context: &VisitContext<'_>) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// let message = get_message("2.5.1", "ACK").expect("can get ACK");
+/// let mut counter = CountFields(0);
+/// walk_message("2.5.1", message, &mut counter);
+/// assert!(counter.0 > 0);
+/// ```
+pub fn walk_message<V: MessageVisitor + ?Sized>(
+    version: &str,
+    message: &'static Message,
+    visitor: &mut V,
+) {
+    let mut walker = Walker {
+        definition: get_definition(version),
+        visitor,
+        groups: Vec::new(),
+    };
+    walker.segments(message.segments, 0, 1, 1);
+}
+
+struct Walker<'v, V: ?Sized> {
+    definition: Option<&'static Definition>,
+    visitor: &'v mut V,
+    groups: Vec<&'static str>,
+}
+
+/// The components of a sub-field's datatype, if it is a composite
+fn composite(subfield: &SubField) -> &'static [SubField] {
+    subfield
+        .datatype_definition()
+        .map(|field| field.subfields)
+        .unwrap_or_default()
+}
+
+impl<V: MessageVisitor + ?Sized> Walker<'_, V> {
+    fn segments(
+        &mut self,
+        segments: &'static [MessageSegment],
+        depth: usize,
+        min: usize,
+        max: usize,
+    ) {
+        for segment in segments {
+            let min = min * segment.min;
+            let max = max.saturating_mul(segment.max);
+            match segment.children {
+                Some(children) => {
+                    self.visitor.enter_group(
+                        segment,
+                        &VisitContext::new(&self.groups, None, depth, min, max),
+                    );
+                    self.groups.push(segment.name);
+                    self.segments(children, depth + 1, min, max);
+                    self.groups.pop();
+                    self.visitor.leave_group(
+                        segment,
+                        &VisitContext::new(&self.groups, None, depth, min, max),
+                    );
+                }
+                None => self.segment(segment, depth, min, max),
+            }
+        }
+    }
+
+    fn segment(&mut self, segment: &'static MessageSegment, depth: usize, min: usize, max: usize) {
+        let definition = self
+            .definition
+            .and_then(|definition| definition.segments.get(segment.name));
+        let segment_context = VisitContext::new(&self.groups, Some(segment.name), depth, min, max);
+        self.visitor
+            .visit_segment(segment, definition, &segment_context);
+
+        let Some(definition) = definition else {
+            return;
+        };
+        for (f, field) in definition.fields.iter().enumerate() {
+            let context = VisitContext {
+                field: Some(f + 1),
+                depth: depth + 1,
+                ..segment_context
+            };
+            self.visitor.visit_field(field, &context);
+
+            for (c, component) in composite(field).iter().enumerate() {
+                let mut context = context;
+                context.depth += 1;
+                context.component = Some(c + 1);
+                self.visitor.visit_component(component, &context);
+
+                // anything nested below sub-components can't be encoded, so isn't visited
+                for (s, subcomponent) in composite(component).iter().enumerate() {
+                    let mut context = context;
+                    context.depth += 1;
+                    context.subcomponent = Some(s + 1);
+                    self.visitor.visit_component(subcomponent, &context);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_message;
+    use alloc::string::{String, ToString};
+
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl MessageVisitor for Recorder {
+        fn enter_group(&mut self, group: &'static MessageSegment, context: &VisitContext<'_>) {
+            self.0.push(format!(
+                "enter {} {} {}..{}",
+                group.name, context.depth, context.min, context.max
+            ));
+        }
+
+        fn leave_group(&mut self, group: &'static MessageSegment, _context: &VisitContext<'_>) {
+            self.0.push(format!("leave {}", group.name));
+        }
+
+        fn visit_segment(
+            &mut self,
+            _segment: &'static MessageSegment,
+            _definition: Option<&'static Segment>,
+            context: &VisitContext<'_>,
+        ) {
+            self.0.push(format!(
+                "segment {context} {} {}..{}",
+                context.depth, context.min, context.max
+            ));
+        }
+
+        fn visit_field(&mut self, _field: &'static SubField, context: &VisitContext<'_>) {
+            self.0.push(context.to_string());
+        }
+
+        fn visit_component(&mut self, _component: &'static SubField, context: &VisitContext<'_>) {
+            self.0.push(format!("{context} {}", context.depth));
+        }
+    }
+
+    #[test]
+    fn can_walk_messages() {
+        let message = get_message("2.5.1", "OML_O21").expect("can get OML_O21");
+        let mut recorder = Recorder::default();
+        walk_message("2.5.1", message, &mut recorder);
+        let visited = recorder.0;
+
+        assert_eq!(visited[0], "segment MSH 0 1..1");
+        assert!(visited.contains(&"MSH-9".to_string()));
+        assert!(visited.contains(&"MSH-9.2 2".to_string()));
+        assert!(visited.contains(&"enter ORDER 0 1..999".to_string()));
+        assert!(visited.contains(&"segment ORDER/NTE 1 0..998001".to_string()));
+        assert_eq!(visited.last().map(String::as_str), Some("leave ORDER"));
+    }
+
+    #[test]
+    fn visits_subcomponents() {
+        let message = get_message("2.5.1", "ADT_A01").expect("can get ADT_A01");
+        let mut recorder = Recorder::default();
+        walk_message("2.5.1", message, &mut recorder);
+        assert!(recorder.0.contains(&"PID-5.1.1 3".to_string()));
+        assert!(!recorder.0.iter().any(|v| v.starts_with("PID-5.1.1.")));
+    }
+}
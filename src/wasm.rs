@@ -0,0 +1,137 @@
+//! JavaScript bindings, available with the `wasm` feature
+//!
+//! Every function returns plain JS objects (or `undefined` when the requested item doesn't
+//! exist) so the results can be used directly from JavaScript without any wrapper classes.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{SubField, TableId, TableType, TableValue, VERSIONS};
+
+fn to_js<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, JsError> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+fn optional_to_js<T: Serialize + ?Sized>(value: Option<&T>) -> Result<JsValue, JsError> {
+    match value {
+        Some(value) => to_js(value),
+        None => Ok(JsValue::UNDEFINED),
+    }
+}
+
+fn table_id(table: &JsValue) -> Option<TableId> {
+    if let Some(id) = table.as_f64() {
+        return (id.fract() == 0.0 && (0.0..=u16::MAX as f64).contains(&id))
+            .then_some(TableId(id as u16));
+    }
+    table.as_string()?.parse().ok()
+}
+
+/// The versions compiled into the module, oldest first
+#[wasm_bindgen]
+pub fn versions() -> Result<JsValue, JsError> {
+    to_js(VERSIONS)
+}
+
+/// Get a field (datatype) for the given version, such as `getField("2.5.1", "XPN")`
+#[wasm_bindgen(js_name = getField)]
+pub fn get_field(version: &str, field: &str) -> Result<JsValue, JsError> {
+    optional_to_js(crate::get_field(version, field))
+}
+
+/// Get a segment for the given version, such as `getSegment("2.5.1", "PID")`
+#[wasm_bindgen(js_name = getSegment)]
+pub fn get_segment(version: &str, segment: &str) -> Result<JsValue, JsError> {
+    optional_to_js(crate::get_segment(version, segment))
+}
+
+/// Get a message for the given version, such as `getMessage("2.5.1", "ADT_A01")`
+#[wasm_bindgen(js_name = getMessage)]
+pub fn get_message(version: &str, message: &str) -> Result<JsValue, JsError> {
+    optional_to_js(crate::get_message(version, message))
+}
+
+#[derive(Serialize)]
+struct JsResolvedPath<'a> {
+    path: String,
+    segment: &'a str,
+    segment_description: &'static str,
+    field: &'static SubField,
+    component: Option<&'static SubField>,
+    subcomponent: Option<&'static SubField>,
+    target: &'static SubField,
+}
+
+/// Resolve a path such as `PID-5.1` for the given version
+///
+/// The result names the segment and includes the field, component and sub-component
+/// definitions along the path, with `target` being the most specific of them.
+#[wasm_bindgen(js_name = resolvePath)]
+pub fn resolve_path(version: &str, path: &str) -> Result<JsValue, JsError> {
+    let resolved = crate::FieldPath::parse(path).and_then(|parsed| {
+        let resolved = crate::resolve_path(version, path)?;
+        Some(JsResolvedPath {
+            path: parsed.to_string(),
+            segment: parsed.segment,
+            segment_description: resolved.segment.description,
+            field: resolved.field,
+            component: resolved.component,
+            subcomponent: resolved.subcomponent,
+            target: resolved.target(),
+        })
+    });
+    optional_to_js(resolved.as_ref())
+}
+
+#[derive(Serialize)]
+struct JsTableValue<'a> {
+    code: &'a str,
+    #[serde(flatten)]
+    value: &'static TableValue,
+}
+
+#[derive(Serialize)]
+struct JsTable {
+    id: String,
+    description: &'static str,
+    #[serde(rename = "type")]
+    table_type: Option<TableType>,
+    values: Vec<JsTableValue<'static>>,
+}
+
+/// Get a table and all of its values. The table may be given as a number (`1`) or a string
+/// (`"0001"`, `"HL70001"`).
+#[wasm_bindgen(js_name = getTable)]
+pub fn get_table(table: JsValue) -> Result<JsValue, JsError> {
+    let table = table_id(&table)
+        .and_then(crate::get_table)
+        .map(|table| JsTable {
+            id: table.id().to_string(),
+            description: table.description(),
+            table_type: table.table_type(),
+            values: table
+                .entries()
+                .map(|(code, value)| JsTableValue { code, value })
+                .collect(),
+        });
+    optional_to_js(table.as_ref())
+}
+
+/// Get the description of a single value in a table, such as `tableValue("0001", "F")`
+#[wasm_bindgen(js_name = tableValue)]
+pub fn table_value(table: JsValue, code: &str) -> Option<String> {
+    let table = crate::get_table(table_id(&table)?)?;
+    table.value(code).map(str::to_string)
+}
+
+/// Get a value in a table along with its status and version metadata
+#[wasm_bindgen(js_name = tableEntry)]
+pub fn table_entry(table: JsValue, code: &str) -> Result<JsValue, JsError> {
+    let entry = table_id(&table)
+        .and_then(crate::get_table)
+        .and_then(|table| table.entry(code))
+        .map(|value| JsTableValue { code, value });
+    optional_to_js(entry.as_ref())
+}
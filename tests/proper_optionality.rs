@@ -1,9 +1,9 @@
-use hl7_definitions::{FieldOptionality, get_segment};
+use hl7_definitions::{get_segment, FieldOptionality};
 
 #[test]
 fn test_proper_optionality() {
     let segment = get_segment("2.3", "MSH").expect("MSH segment");
-    let st_field = segment.fields.iter().nth(7).expect("MSH.8");
+    let st_field = segment.fields.get(7).expect("MSH.8");
     assert_eq!(st_field.description, "Security");
     assert_eq!(st_field.optionality, FieldOptionality::Optional);
 }